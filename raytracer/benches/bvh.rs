@@ -0,0 +1,51 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use raytracer::{
+    bvh::Bvh,
+    hittable::Hittable,
+    ray::Ray,
+    sampler::IndependentSampler,
+    scene::{random_world, Scene},
+};
+
+fn camera_rays(scene: &Scene, width: usize, height: usize) -> Vec<Ray> {
+    let mut sampler = IndependentSampler::new(0);
+
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            scene.camera.get_ray(
+                x as f64 / (width as f64 - 1.),
+                y as f64 / (height as f64 - 1.),
+                &mut sampler,
+            )
+        })
+        .collect()
+}
+
+fn random_world_hits(c: &mut Criterion) {
+    let scene = Scene::new();
+    let rays = camera_rays(&scene, 120, 80);
+    let world = random_world(0);
+    let bvh = Bvh::new(world.objects.clone());
+
+    let mut group = c.benchmark_group("random_world");
+    group.bench_function("linear", |b| {
+        b.iter(|| {
+            for ray in &rays {
+                black_box(world.hit(ray, 0.001, f64::INFINITY));
+            }
+        })
+    });
+    group.bench_function("bvh", |b| {
+        b.iter(|| {
+            for ray in &rays {
+                black_box(bvh.hit(ray, 0.001, f64::INFINITY));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, random_world_hits);
+criterion_main!(benches);
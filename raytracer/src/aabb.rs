@@ -0,0 +1,80 @@
+use crate::{point3d::Point3D, ray::Ray};
+
+/// Axis-aligned bounding box
+#[derive(Clone, Copy)]
+pub struct Aabb {
+    pub min: Point3D,
+    pub max: Point3D,
+}
+
+impl Aabb {
+    pub fn new(min: Point3D, max: Point3D) -> Self {
+        Self { min, max }
+    }
+
+    /// smallest box that contains both boxes
+    pub fn surrounding(&self, other: &Aabb) -> Self {
+        Self {
+            min: Point3D::new(
+                self.min[0].min(other.min[0]),
+                self.min[1].min(other.min[1]),
+                self.min[2].min(other.min[2]),
+            ),
+            max: Point3D::new(
+                self.max[0].max(other.max[0]),
+                self.max[1].max(other.max[1]),
+                self.max[2].max(other.max[2]),
+            ),
+        }
+    }
+
+    pub fn centroid(&self) -> Point3D {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        2. * (d[0] * d[1] + d[1] * d[2] + d[2] * d[0])
+    }
+
+    /// slab test: does the ray pass through the box anywhere in [t_min, t_max]?
+    pub fn hit(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        for axis in 0..3 {
+            let inverse_direction = 1. / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inverse_direction;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inverse_direction;
+
+            if inverse_direction < 0. {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+
+            if t_max <= t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit() {
+        let aabb = Aabb::new(Point3D::new(-1., -1., -1.), Point3D::new(1., 1., 1.));
+
+        let towards = Ray::new(Point3D::new(0., 0., 5.), Point3D::new(0., 0., -1.));
+        let away = Ray::new(Point3D::new(0., 0., 5.), Point3D::new(0., 0., 1.));
+        let past = Ray::new(Point3D::new(2., 0., 5.), Point3D::new(0., 0., -1.));
+
+        assert!(aabb.hit(&towards, 0.001, f64::INFINITY));
+        assert!(!aabb.hit(&towards, 0.001, 3.));
+        assert!(!aabb.hit(&away, 0.001, f64::INFINITY));
+        assert!(!aabb.hit(&past, 0.001, f64::INFINITY));
+    }
+}
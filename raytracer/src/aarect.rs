@@ -0,0 +1,193 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    point3d::Point3D,
+    ray::Ray,
+};
+
+/// padding for the bounding box in the rectangle's normal direction
+const THICKNESS: f64 = 1e-4;
+
+/// Intersect the rectangle `[a0, a1] x [b0, b1]` lying in the plane `c = k`,
+/// where `(a, b, c)` are the coordinate axes in `axes`.
+///
+/// The normal points towards positive `c`.
+fn hit_rect<'a>(
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+    [a, b, c]: [usize; 3],
+    [a0, a1, b0, b1, k]: [f64; 5],
+    material: &'a Material,
+) -> Option<HitRecord<'a>> {
+    let t = (k - ray.origin[c]) / ray.direction[c];
+    if !(t_min..=t_max).contains(&t) {
+        return None;
+    }
+
+    let p = ray.at(t);
+    if p[a] < a0 || p[a] > a1 || p[b] < b0 || p[b] > b1 {
+        return None;
+    }
+
+    let mut normal = [0.; 3];
+    normal[c] = 1.;
+    let normal = Point3D::new(normal[0], normal[1], normal[2]);
+    let front_face = ray.direction[c] < 0.0;
+
+    Some(HitRecord {
+        point: p,
+        normal: if front_face { normal } else { -normal },
+        t,
+        u: (p[a] - a0) / (a1 - a0),
+        v: (p[b] - b0) / (b1 - b0),
+        front_face,
+        material,
+    })
+}
+
+fn rect_bounding_box([a, b, c]: [usize; 3], [a0, a1, b0, b1, k]: [f64; 5]) -> Aabb {
+    let mut min = [0.; 3];
+    let mut max = [0.; 3];
+    (min[a], max[a]) = (a0, a1);
+    (min[b], max[b]) = (b0, b1);
+    (min[c], max[c]) = (k - THICKNESS, k + THICKNESS);
+
+    Aabb::new(
+        Point3D::new(min[0], min[1], min[2]),
+        Point3D::new(max[0], max[1], max[2]),
+    )
+}
+
+/// Rectangle in the plane `z = k`
+#[derive(Clone)]
+pub struct XyRect {
+    pub x0: f64,
+    pub x1: f64,
+    pub y0: f64,
+    pub y1: f64,
+    pub k: f64,
+    pub material: Material,
+}
+
+impl XyRect {
+    pub fn new(x0: f64, x1: f64, y0: f64, y1: f64, k: f64, material: Material) -> Self {
+        Self {
+            x0,
+            x1,
+            y0,
+            y1,
+            k,
+            material,
+        }
+    }
+}
+
+impl Hittable for XyRect {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_rect(
+            ray,
+            t_min,
+            t_max,
+            [0, 1, 2],
+            [self.x0, self.x1, self.y0, self.y1, self.k],
+            &self.material,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(rect_bounding_box(
+            [0, 1, 2],
+            [self.x0, self.x1, self.y0, self.y1, self.k],
+        ))
+    }
+}
+
+/// Rectangle in the plane `y = k`
+#[derive(Clone)]
+pub struct XzRect {
+    pub x0: f64,
+    pub x1: f64,
+    pub z0: f64,
+    pub z1: f64,
+    pub k: f64,
+    pub material: Material,
+}
+
+impl XzRect {
+    pub fn new(x0: f64, x1: f64, z0: f64, z1: f64, k: f64, material: Material) -> Self {
+        Self {
+            x0,
+            x1,
+            z0,
+            z1,
+            k,
+            material,
+        }
+    }
+}
+
+impl Hittable for XzRect {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_rect(
+            ray,
+            t_min,
+            t_max,
+            [0, 2, 1],
+            [self.x0, self.x1, self.z0, self.z1, self.k],
+            &self.material,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(rect_bounding_box(
+            [0, 2, 1],
+            [self.x0, self.x1, self.z0, self.z1, self.k],
+        ))
+    }
+}
+
+/// Rectangle in the plane `x = k`
+#[derive(Clone)]
+pub struct YzRect {
+    pub y0: f64,
+    pub y1: f64,
+    pub z0: f64,
+    pub z1: f64,
+    pub k: f64,
+    pub material: Material,
+}
+
+impl YzRect {
+    pub fn new(y0: f64, y1: f64, z0: f64, z1: f64, k: f64, material: Material) -> Self {
+        Self {
+            y0,
+            y1,
+            z0,
+            z1,
+            k,
+            material,
+        }
+    }
+}
+
+impl Hittable for YzRect {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_rect(
+            ray,
+            t_min,
+            t_max,
+            [1, 2, 0],
+            [self.y0, self.y1, self.z0, self.z1, self.k],
+            &self.material,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(rect_bounding_box(
+            [1, 2, 0],
+            [self.y0, self.y1, self.z0, self.z1, self.k],
+        ))
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{color::Color, framebuffer::Framebuffer};
+
+/// identifies checkpoint files, followed by the format version
+const MAGIC: &[u8; 8] = b"RTCHKPT3";
+
+/// bytes of the magic, size, seed, passes and fingerprint before the pixels
+const HEADER_SIZE: u64 = 8 + 5 * 8;
+
+/// bytes of a pixel: the sum of its colors, of its squares and its samples
+const PIXEL_SIZE: u64 = 3 * 4 + 8 + 4;
+
+/// a pixel that has not converged gets at most this many times the samples of
+/// a regular pass
+const MAX_BOOST: usize = 8;
+
+/// Stop sampling pixels once the estimated error of their mean luminance
+/// drops below `threshold`, relative to that mean
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdaptiveSampling {
+    pub threshold: f64,
+    /// samples every pixel gets before its variance is trusted
+    pub min_samples: u32,
+}
+
+/// Running sums of the radiance samples of every pixel, so that a render can
+/// be refined in passes and continued later from a checkpoint
+pub struct Accumulator {
+    pub width: usize,
+    pub height: usize,
+    sums: Vec<Color>,
+    /// sums of the squared luminance of the samples, for their variance
+    squares: Vec<f64>,
+    samples: Vec<u32>,
+    /// seeds the random numbers of every pass
+    pub seed: u64,
+    /// number of passes rendered so far
+    pub passes: u64,
+    /// identifies the scene and settings the samples were taken with, see
+    /// `fingerprint`
+    pub fingerprint: u64,
+}
+
+impl Accumulator {
+    pub fn new(width: usize, height: usize, seed: u64) -> Self {
+        Self {
+            width,
+            height,
+            sums: vec![Color::black(); width * height],
+            squares: vec![0.; width * height],
+            samples: vec![0; width * height],
+            seed,
+            passes: 0,
+            fingerprint: 0,
+        }
+    }
+
+    pub fn with_fingerprint(mut self, fingerprint: u64) -> Self {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    /// fewest samples taken by any pixel
+    pub fn samples_per_pixel(&self) -> u32 {
+        self.samples.iter().copied().min().unwrap_or(0)
+    }
+
+    /// samples taken by every pixel, row by row
+    pub fn sample_counts(&self) -> &[u32] {
+        &self.samples
+    }
+
+    /// samples taken over all pixels
+    pub fn total_samples(&self) -> u64 {
+        self.samples.iter().map(|&s| s as u64).sum()
+    }
+
+    /// add `samples` new samples to pixel (x, y), given the `sum` of their
+    /// colors and the sum of their `squares` of luminance
+    pub fn add(&mut self, x: usize, y: usize, sum: Color, squares: f64, samples: u32) {
+        let i = y * self.width + x;
+        self.sums[i] += sum;
+        self.squares[i] += squares;
+        self.samples[i] += samples;
+    }
+
+    /// estimated standard error of the mean luminance of pixel `i`
+    fn standard_error(&self, i: usize) -> f64 {
+        let n = self.samples[i] as f64;
+        if n < 2. {
+            return f64::INFINITY;
+        }
+
+        let mean = self.sums[i].luminance() as f64 / n;
+        let variance = (self.squares[i] / n - mean * mean).max(0.) * n / (n - 1.);
+
+        (variance / n).sqrt()
+    }
+
+    /// Samples for every pixel in the next pass: the pass's budget of
+    /// `samples_per_pass` per pixel is shared by the pixels that have not
+    /// converged yet. All zero once every pixel has converged.
+    pub fn adaptive_plan(&self, adaptive: &AdaptiveSampling, samples_per_pass: usize) -> Vec<u32> {
+        let errors: Vec<f64> = (0..self.samples.len())
+            .map(|i| self.standard_error(i))
+            .collect();
+
+        let active: Vec<bool> = (0..self.samples.len())
+            .map(|i| {
+                let (x, y) = (i % self.width, i / self.width);
+                let mean = self.sums[i].luminance() as f64 / self.samples[i].max(1) as f64;
+
+                // a pixel whose few samples all missed the light looks converged,
+                // so trust the noisiest of its neighbours instead
+                let error = (y.saturating_sub(1)..(y + 2).min(self.height))
+                    .flat_map(|y| {
+                        (x.saturating_sub(1)..(x + 2).min(self.width)).map(move |x| (x, y))
+                    })
+                    .map(|(x, y)| errors[y * self.width + x])
+                    .fold(0., f64::max);
+
+                // dark pixels are judged against a floor, their relative error is meaningless
+                self.samples[i] < adaptive.min_samples
+                    || error > adaptive.threshold * mean.max(0.01)
+            })
+            .collect();
+        let count = active.iter().filter(|&&a| a).count();
+        if count == 0 {
+            return vec![0; active.len()];
+        }
+
+        let share = (samples_per_pass * active.len())
+            .div_ceil(count)
+            .min(samples_per_pass * MAX_BOOST) as u32;
+
+        active
+            .into_iter()
+            .map(|a| if a { share } else { 0 })
+            .collect()
+    }
+
+    /// Samples taken per pixel as a black-red-yellow-white heat map, relative to
+    /// the pixel with the most samples
+    pub fn sample_heatmap(&self) -> Framebuffer {
+        let max = self.samples.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+
+        for (pixel, &samples) in framebuffer.pixels_mut().iter_mut().zip(&self.samples) {
+            let heat = 3. * samples as f32 / max;
+            *pixel = Color(
+                heat.clamp(0., 1.),
+                (heat - 1.).clamp(0., 1.),
+                (heat - 2.).clamp(0., 1.),
+            );
+        }
+
+        framebuffer
+    }
+
+    /// average radiance of every pixel
+    pub fn framebuffer(&self) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+
+        let averages = self.sums.iter().zip(&self.samples);
+        for (pixel, (&sum, &samples)) in framebuffer.pixels_mut().iter_mut().zip(averages) {
+            *pixel = sum / samples.max(1) as f32;
+        }
+
+        framebuffer
+    }
+
+    /// Write a checkpoint, replacing the file only once it is complete
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let partial = path.with_extension("partial");
+
+        let mut file = BufWriter::new(File::create(&partial)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&(self.width as u64).to_le_bytes())?;
+        file.write_all(&(self.height as u64).to_le_bytes())?;
+        file.write_all(&self.seed.to_le_bytes())?;
+        file.write_all(&self.passes.to_le_bytes())?;
+        file.write_all(&self.fingerprint.to_le_bytes())?;
+        for i in 0..self.samples.len() {
+            let sum = self.sums[i];
+            for channel in [sum.0, sum.1, sum.2] {
+                file.write_all(&channel.to_le_bytes())?;
+            }
+            file.write_all(&self.squares[i].to_le_bytes())?;
+            file.write_all(&self.samples[i].to_le_bytes())?;
+        }
+        file.into_inner()?.sync_all()?;
+
+        std::fs::rename(partial, path)
+    }
+
+    /// Read a checkpoint written by `save`
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
+        let mut file = BufReader::new(file);
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a checkpoint file"));
+        }
+
+        let width = read_u64(&mut file)?;
+        let height = read_u64(&mut file)?;
+        // check the size against the file before allocating anything for it
+        let expected = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(PIXEL_SIZE))
+            .and_then(|pixels| pixels.checked_add(HEADER_SIZE));
+        if expected != Some(length) {
+            return Err(invalid("checkpoint file is truncated or corrupt"));
+        }
+
+        let mut accumulator = Self::new(width as usize, height as usize, read_u64(&mut file)?);
+        accumulator.passes = read_u64(&mut file)?;
+        accumulator.fingerprint = read_u64(&mut file)?;
+
+        for i in 0..accumulator.samples.len() {
+            accumulator.sums[i] = Color(
+                read_f32(&mut file)?,
+                read_f32(&mut file)?,
+                read_f32(&mut file)?,
+            );
+            accumulator.squares[i] = f64::from_le_bytes(read_u64(&mut file)?.to_le_bytes());
+            accumulator.samples[i] = read_u32(&mut file)?;
+        }
+
+        Ok(accumulator)
+    }
+}
+
+/// Identifies a scene and the settings it is rendered with, given as text,
+/// e.g. to tell whether a checkpoint belongs to a render. Unlike the standard
+/// library's hashers, the same text gives the same fingerprint on every
+/// platform and toolchain.
+pub fn fingerprint(text: &str) -> u64 {
+    // 64-bit FNV-1a
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoint() {
+        let mut accumulator = Accumulator::new(3, 2, 42).with_fingerprint(fingerprint("scene"));
+        accumulator.passes = 5;
+        for y in 0..2 {
+            for x in 0..3 {
+                accumulator.add(x, y, Color(x as f32, y as f32, 2.5), 1., 10);
+            }
+        }
+
+        // unique to the process, test runs can overlap
+        let path = std::env::temp_dir().join(format!(
+            "raytracer_accumulator_test_{}.checkpoint",
+            std::process::id()
+        ));
+        accumulator.save(&path).unwrap();
+        let loaded = Accumulator::load(&path).unwrap();
+
+        assert_eq!((loaded.width, loaded.height), (3, 2));
+        assert_eq!((loaded.seed, loaded.passes), (42, 5));
+        assert_eq!(loaded.fingerprint, fingerprint("scene"));
+        assert_ne!(loaded.fingerprint, fingerprint("scene "));
+        assert_eq!(loaded.samples_per_pixel(), 10);
+        assert_eq!(loaded.framebuffer().get(2, 1), Color(0.2, 0.1, 0.25));
+        assert_eq!(loaded.squares, accumulator.squares);
+
+        // a size that does not fit the file is rejected before allocating it
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        let error = Accumulator::load(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn adaptive_plan() {
+        let mut accumulator = Accumulator::new(4, 1, 0);
+        // flat pixels of 0.5 next to a noisy one, alternating between 0 and 1
+        for x in 0..3 {
+            accumulator.add(x, 0, Color(8., 8., 8.), 16. * 0.25, 16);
+        }
+        accumulator.add(3, 0, Color(8., 8., 8.), 8., 16);
+
+        // the flat neighbour of the noisy pixel is not trusted either
+        let adaptive = AdaptiveSampling {
+            threshold: 0.05,
+            min_samples: 16,
+        };
+        assert_eq!(accumulator.adaptive_plan(&adaptive, 4), [0, 0, 8, 8]);
+
+        let adaptive = AdaptiveSampling {
+            min_samples: 32,
+            ..adaptive
+        };
+        assert_eq!(accumulator.adaptive_plan(&adaptive, 4), [4, 4, 4, 4]);
+    }
+}
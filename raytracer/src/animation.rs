@@ -0,0 +1,370 @@
+use std::{
+    ops::{Add, Mul, RangeInclusive, Sub},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    bvh::Bvh,
+    camera::{Camera, CameraModel, Equirectangular, Fisheye, Lens, Orthographic, Projection},
+    hittable::Hittable,
+    hittable_list::SharedHittable,
+    matrix::Matrix4,
+    point3d::{DotProduct, Length, Normalize, Point3D},
+    sampler::IndependentSampler,
+    scene::Scene,
+    transform::Transformed,
+};
+
+/// How a track gets from one keyframe to the next
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Interpolation {
+    /// in a straight line, with kinks at the keyframes
+    #[default]
+    Linear,
+    /// along a Catmull-Rom spline, smooth through the keyframes
+    Spline,
+}
+
+/// Values at keyframes, interpolated in between and held before the first
+/// and after the last keyframe
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+    /// frame and value, by frame
+    keys: Vec<(f64, T)>,
+    interpolation: Interpolation,
+}
+
+impl<T> Track<T> {
+    /// # Panics
+    ///
+    /// Panics without any keys
+    pub fn new(mut keys: Vec<(f64, T)>, interpolation: Interpolation) -> Self {
+        assert!(!keys.is_empty(), "a track needs at least one key");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self {
+            keys,
+            interpolation,
+        }
+    }
+
+    /// the same value on every frame
+    pub fn constant(value: T) -> Self {
+        Self::new(vec![(0., value)], Interpolation::Linear)
+    }
+}
+
+impl<T> Track<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T>,
+    f64: Mul<T, Output = T>,
+{
+    pub fn at(&self, frame: f64) -> T {
+        let keys = &self.keys;
+        let i = keys.partition_point(|&(f, _)| f <= frame);
+        if i == 0 {
+            return keys[0].1;
+        }
+        if i == keys.len() {
+            return keys[i - 1].1;
+        }
+
+        let ((f0, p0), (f1, p1)) = (keys[i - 1], keys[i]);
+        let s = (frame - f0) / (f1 - f0);
+        match self.interpolation {
+            Interpolation::Linear => p0 + s * (p1 - p0),
+            Interpolation::Spline => {
+                // cubic Hermite curve with the slopes of the neighbouring keys
+                let [h0, m0, h1, m1] = hermite(s, f1 - f0);
+                h0 * p0 + m0 * self.slope(i - 1) + h1 * p1 + m1 * self.slope(i)
+            }
+        }
+    }
+
+    /// change per frame at key `i`, one-sided at the first and last key
+    fn slope(&self, i: usize) -> T {
+        let (f0, p0) = self.keys[i.saturating_sub(1)];
+        let (f1, p1) = self.keys[(i + 1).min(self.keys.len() - 1)];
+
+        (1. / (f1 - f0)) * (p1 - p0)
+    }
+}
+
+/// weights of the start and end values and slopes of a cubic Hermite curve
+/// over `span` frames, at `s` between 0 and 1
+fn hermite(s: f64, span: f64) -> [f64; 4] {
+    let (s2, s3) = (s * s, s * s * s);
+
+    [
+        2. * s3 - 3. * s2 + 1.,
+        (s3 - 2. * s2 + s) * span,
+        -2. * s3 + 3. * s2,
+        (s3 - s2) * span,
+    ]
+}
+
+/// The parameters of a camera over the frames, most of them as tracks
+pub struct CameraRig {
+    pub projection: Projection,
+    pub look_from: Track<Point3D>,
+    pub look_at: Track<Point3D>,
+    pub vup: Point3D,
+    /// vertical field of view in degrees, of perspective and fisheye cameras
+    /// without a `lens`
+    pub vfov: Track<f64>,
+    /// lens diameter of perspective cameras without a `lens`
+    pub aperture: f64,
+    /// field of view and aperture of perspective cameras given by their lens
+    pub lens: Option<Lens>,
+    /// the distance from `look_from` to `look_at` if there is none
+    pub focus_distance: Option<Track<f64>>,
+    /// Point (s, t) of the image, from the lower left corner, whose first hit
+    /// is in focus on every frame. Overrides `focus_distance` wherever it hits.
+    pub autofocus: Option<(f64, f64)>,
+    /// shutter open and close times
+    pub shutter: (f64, f64),
+}
+
+impl CameraRig {
+    /// the camera on `frame`, focused on the `objects` if it has autofocus
+    pub fn camera(
+        &self,
+        frame: f64,
+        aspect_ratio: f64,
+        objects: &dyn Hittable,
+    ) -> Box<dyn CameraModel> {
+        let look_from = self.look_from.at(frame);
+        let look_at = self.look_at.at(frame);
+        let (open, close) = self.shutter;
+
+        match self.projection {
+            Projection::Perspective => Box::new(
+                self.perspective(
+                    frame,
+                    aspect_ratio,
+                    self.lens,
+                    self.aperture,
+                    self.focus_distance(frame, aspect_ratio, objects),
+                )
+                .with_shutter(open, close),
+            ),
+            Projection::Orthographic { height } => Box::new(
+                Orthographic::new(look_from, look_at, self.vup, height, aspect_ratio)
+                    .with_shutter(open, close),
+            ),
+            Projection::Fisheye => Box::new(
+                Fisheye::new(
+                    look_from,
+                    look_at,
+                    self.vup,
+                    self.vfov.at(frame),
+                    aspect_ratio,
+                )
+                .with_shutter(open, close),
+            ),
+            Projection::Equirectangular => Box::new(
+                Equirectangular::new(look_from, look_at, self.vup).with_shutter(open, close),
+            ),
+        }
+    }
+
+    /// perspective camera on `frame`, with the `lens` if there is one or else
+    /// the `vfov` and `aperture`
+    fn perspective(
+        &self,
+        frame: f64,
+        aspect_ratio: f64,
+        lens: Option<Lens>,
+        aperture: f64,
+        focus_distance: f64,
+    ) -> Camera {
+        let look_from = self.look_from.at(frame);
+        let look_at = self.look_at.at(frame);
+
+        match lens {
+            Some(lens) => Camera::from_lens(
+                look_from,
+                look_at,
+                self.vup,
+                lens,
+                aspect_ratio,
+                focus_distance,
+            ),
+            None => Camera::new(
+                look_from,
+                look_at,
+                self.vup,
+                self.vfov.at(frame),
+                aspect_ratio,
+                aperture,
+                focus_distance,
+            ),
+        }
+    }
+
+    /// Distance from `look_from` to the plane in focus on `frame`: that of
+    /// the autofocus point's first hit on the `objects`, or else the
+    /// `focus_distance` or the distance to `look_at`
+    pub fn focus_distance(&self, frame: f64, aspect_ratio: f64, objects: &dyn Hittable) -> f64 {
+        let look_from = self.look_from.at(frame);
+        let look_at = self.look_at.at(frame);
+
+        let autofocus = self.autofocus.and_then(|(s, t)| {
+            let pinhole_lens = self.lens.map(|lens| Lens {
+                f_number: f64::INFINITY,
+                ..lens
+            });
+            let pinhole = self.perspective(frame, aspect_ratio, pinhole_lens, 0., 1.);
+            let ray = pinhole.get_ray(s, t, &mut IndependentSampler::new(0));
+            let hit_record = objects.hit(&ray, 0.001, f64::INFINITY)?;
+
+            // the plane of focus is square to the view direction
+            Some((hit_record.point - look_from).dot(&(look_at - look_from).normalize()))
+        });
+
+        match (autofocus, &self.focus_distance) {
+            (Some(distance), _) => distance,
+            (None, Some(track)) => track.at(frame),
+            (None, None) => (look_from - look_at).length(),
+        }
+    }
+}
+
+/// Placement of an object over the frames, see `Matrix4::placement`
+pub struct TransformTrack {
+    pub translate: Track<Point3D>,
+    /// degrees around the x, y and z axes
+    pub rotate: Track<Point3D>,
+    pub scale: Track<Point3D>,
+}
+
+impl TransformTrack {
+    pub fn matrix(&self, frame: f64) -> Matrix4 {
+        Matrix4::placement(
+            self.translate.at(frame),
+            self.rotate.at(frame),
+            self.scale.at(frame),
+        )
+    }
+}
+
+pub enum AnimatedObject {
+    /// in the same place on every frame
+    Fixed(SharedHittable),
+    /// an object in its own space, placed anew on every frame
+    Keyframed {
+        object: SharedHittable,
+        transform: TransformTrack,
+    },
+}
+
+impl AnimatedObject {
+    /// The object as placed on `frame`. `None` where the transform between
+    /// two keyframes is not invertible, e.g. a scale passing through zero:
+    /// the object is flat there and not rendered.
+    pub fn at(&self, frame: f64) -> Option<SharedHittable> {
+        match self {
+            AnimatedObject::Fixed(object) => Some(object.clone()),
+            AnimatedObject::Keyframed { object, transform } => {
+                let placed = Transformed::try_new(object.clone(), transform.matrix(frame))?;
+                Some(Arc::new(placed))
+            }
+        }
+    }
+}
+
+/// A camera and objects that move over a range of frames. The scene is set up
+/// once, and only the camera and the BVH are rebuilt for every frame.
+pub struct Animation {
+    /// first and last frame to render
+    pub frames: RangeInclusive<u32>,
+    pub camera: CameraRig,
+    /// every object of the scene, in the order of `Scene::objects`
+    pub objects: Vec<AnimatedObject>,
+}
+
+impl Animation {
+    /// Move the camera and objects of `scene` to where they are on `frame`
+    pub fn apply(&self, scene: &mut Scene, frame: u32) {
+        let frame = frame as f64;
+
+        scene.objects = Bvh::new(self.objects.iter().filter_map(|o| o.at(frame)).collect());
+        scene.camera = self
+            .camera
+            .camera(frame, scene.aspect_ratio, &scene.objects);
+    }
+}
+
+/// `output` with the frame number appended to its name, e.g. `frame_0001.png`
+/// for frame 1 of `frame.png`
+pub fn frame_path<P: AsRef<Path>>(output: P, frame: u32) -> PathBuf {
+    let output = output.as_ref();
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+
+    match output.extension() {
+        Some(extension) => {
+            output.with_file_name(format!("{stem}_{frame:04}.{}", extension.to_string_lossy()))
+        }
+        None => output.with_file_name(format!("{stem}_{frame:04}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        material::{Lambertian, Material},
+        sphere::Sphere,
+    };
+
+    #[test]
+    fn interpolation() {
+        let keys = vec![(10., 4.), (0., 0.), (20., 4.)];
+        let linear = Track::new(keys.clone(), Interpolation::Linear);
+        assert_eq!(linear.at(-5.), 0.);
+        assert_eq!(linear.at(5.), 2.);
+        assert_eq!(linear.at(15.), 4.);
+        assert_eq!(linear.at(25.), 4.);
+
+        // the spline passes through the keys, but overshoots the flat stretch
+        let spline = Track::new(keys, Interpolation::Spline);
+        assert_eq!(spline.at(10.), 4.);
+        assert!(spline.at(13.) > 4.);
+        assert_eq!(spline.at(20.), 4.);
+
+        assert_eq!(
+            frame_path("out/frame.png", 7),
+            Path::new("out/frame_0007.png")
+        );
+    }
+
+    #[test]
+    fn singular_between_keys() {
+        // mirrored from frame 1 to 3, flat on frame 2
+        let sphere = Sphere::new(
+            Point3D::new(0., 0., 0.),
+            1.,
+            Material::Lambertian(Lambertian::new(Color(0.5, 0.5, 0.5))),
+        );
+        let object = AnimatedObject::Keyframed {
+            object: Arc::new(sphere),
+            transform: TransformTrack {
+                translate: Track::constant(Point3D::new(0., 0., 0.)),
+                rotate: Track::constant(Point3D::new(0., 0., 0.)),
+                scale: Track::new(
+                    vec![
+                        (1., Point3D::new(1., 1., 1.)),
+                        (3., Point3D::new(-1., 1., 1.)),
+                    ],
+                    Interpolation::Linear,
+                ),
+            },
+        };
+
+        assert!(object.at(1.).is_some());
+        assert!(object.at(2.).is_none());
+        assert!(object.at(3.).is_some());
+    }
+}
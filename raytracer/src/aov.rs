@@ -0,0 +1,258 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+
+use crate::{color::Color, point3d::Point3D, render::camera_ray, sampler::Sampler, scene::Scene};
+
+/// camera rays per pixel for the feature buffers, enough to antialias edges
+const FEATURE_SAMPLES: usize = 4;
+
+/// First-hit surface properties of every pixel, averaged over a few camera
+/// rays; the geometric ones only over the rays that hit something. Written out
+/// as arbitrary output variables (AOVs) for compositing, and unlike the
+/// radiance (nearly) free of noise, so they tell the denoiser where the edges
+/// are that must stay sharp.
+pub struct Features {
+    pub width: usize,
+    pub height: usize,
+    /// base color of the surface, or the background for rays that miss
+    pub(crate) albedo: Vec<Color>,
+    /// world space normal, zero where every ray misses
+    pub(crate) normals: Vec<Point3D>,
+    /// distance along the camera ray, zero where every ray misses
+    pub(crate) depth: Vec<f64>,
+    /// world space hit point, zero where every ray misses
+    pub(crate) positions: Vec<Point3D>,
+    /// index of the object most rays hit in `Scene::objects`
+    pub(crate) object_ids: Vec<Option<usize>>,
+}
+
+/// first-hit properties of one camera ray, the geometric ones only if it hits
+struct Sample {
+    albedo: Color,
+    hit: Option<Hit>,
+    object_id: Option<usize>,
+}
+
+struct Hit {
+    normal: Point3D,
+    depth: f64,
+    position: Point3D,
+}
+
+impl Features {
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        let origin = Point3D::new(0., 0., 0.);
+
+        Self {
+            width,
+            height,
+            albedo: vec![Color::black(); width * height],
+            normals: vec![origin; width * height],
+            depth: vec![0.; width * height],
+            positions: vec![origin; width * height],
+            object_ids: vec![None; width * height],
+        }
+    }
+
+    /// trace the first hit of a few camera rays through every pixel
+    pub fn render(scene: &Scene) -> Self {
+        let pixels: Vec<Vec<Sample>> = (0..scene.height)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                let mut sampler = scene.sampler.build(0, FEATURE_SAMPLES);
+                (0..scene.width)
+                    .map(|x| {
+                        (0..FEATURE_SAMPLES as u32)
+                            .map(|index| {
+                                sampler.start_pixel_sample(x, y, index);
+                                Self::sample(scene, x, y, sampler.as_mut())
+                            })
+                            .collect()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let mut features = Self::new(scene.width, scene.height);
+        for (i, samples) in pixels.iter().enumerate() {
+            for sample in samples {
+                features.albedo[i] += sample.albedo / samples.len() as f32;
+            }
+
+            // a miss has no depth, counting it as zero would pull edges closer
+            let hits: Vec<&Hit> = samples.iter().filter_map(|s| s.hit.as_ref()).collect();
+            let n = hits.len() as f64;
+            for hit in &hits {
+                features.normals[i] = features.normals[i] + hit.normal / n;
+                features.depth[i] += hit.depth / n;
+                features.positions[i] = features.positions[i] + hit.position / n;
+            }
+
+            // ids cannot be averaged, take the one seen most often
+            features.object_ids[i] = samples
+                .iter()
+                .map(|sample| sample.object_id)
+                .max_by_key(|&id| samples.iter().filter(|s| s.object_id == id).count())
+                .flatten();
+        }
+
+        features
+    }
+
+    fn sample(scene: &Scene, x: usize, y: usize, sampler: &mut dyn Sampler) -> Sample {
+        let ray = camera_ray(scene, x, y, sampler);
+
+        match scene.objects.hit_object(&ray, 0.001, f64::INFINITY) {
+            Some((i, hit_record)) => Sample {
+                albedo: hit_record.material.albedo(&hit_record),
+                hit: Some(Hit {
+                    normal: hit_record.normal,
+                    depth: hit_record.t,
+                    position: hit_record.point,
+                }),
+                object_id: Some(i),
+            },
+            None => Sample {
+                albedo: scene.background.color(&ray),
+                hit: None,
+                object_id: None,
+            },
+        }
+    }
+
+    /// Write every pass as a PFM file next to the image `output`, e.g.
+    /// `render.depth.pfm` for `render.png`, with the number of `samples` per
+    /// pixel as one more pass. Object ids are -1 where the rays missed.
+    pub fn write<P: AsRef<Path>>(&self, output: P, samples: &[u32]) -> io::Result<Vec<PathBuf>> {
+        let vector = |p: &Point3D| [p[0] as f32, p[1] as f32, p[2] as f32];
+        let passes: [(&str, usize, Vec<f32>); 6] = [
+            ("depth", 1, self.depth.iter().map(|&d| d as f32).collect()),
+            ("normal", 3, self.normals.iter().flat_map(vector).collect()),
+            (
+                "position",
+                3,
+                self.positions.iter().flat_map(vector).collect(),
+            ),
+            (
+                "albedo",
+                3,
+                self.albedo.iter().flat_map(|c| [c.0, c.1, c.2]).collect(),
+            ),
+            (
+                "object_id",
+                1,
+                self.object_ids
+                    .iter()
+                    .map(|id| id.map_or(-1., |id| id as f32))
+                    .collect(),
+            ),
+            ("samples", 1, samples.iter().map(|&s| s as f32).collect()),
+        ];
+
+        let output = output.as_ref();
+        let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+        passes
+            .into_iter()
+            .map(|(name, channels, data)| {
+                let path = output.with_file_name(format!("{stem}.{name}.pfm"));
+                write_pfm(&path, self.width, self.height, channels, &data)?;
+                Ok(path)
+            })
+            .collect()
+    }
+}
+
+/// Portable float map, little-endian: one (`Pf`) or three (`PF`) channels,
+/// rows from the bottom up
+fn write_pfm(
+    path: &Path,
+    width: usize,
+    height: usize,
+    channels: usize,
+    data: &[f32],
+) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    let kind = if channels == 1 { "Pf" } else { "PF" };
+    write!(file, "{kind}\n{width} {height}\n-1.0\n")?;
+
+    for row in data.chunks(width * channels).rev() {
+        for value in row {
+            file.write_all(&value.to_le_bytes())?;
+        }
+    }
+
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = r#"
+[image]
+width = 20
+height = 20
+samples_per_pixel = 1
+max_depth = 2
+
+[camera]
+look_from = [0.0, 0.0, 1.0]
+look_at = [0.0, 0.0, 0.0]
+vfov = 90.0
+
+[materials.red]
+type = "lambertian"
+albedo = [1.0, 0.0, 0.0]
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, -1.0]
+radius = 0.2
+material = "red"
+
+[[objects]]
+type = "xy_rect"
+x = [-0.5, 0.5]
+y = [-0.5, 0.5]
+z = -0.5
+material = "red"
+"#;
+
+    #[test]
+    fn first_hit() {
+        let scene = Scene::parse(SCENE).unwrap();
+        let features = Features::render(&scene);
+
+        // the rectangle hides the sphere in the middle, the corners see the sky
+        let center = 10 * 20 + 10;
+        assert_eq!(features.object_ids[center], Some(1));
+        assert_eq!(features.object_ids[0], None);
+        assert!((features.depth[center] - 1.5).abs() < 0.05);
+        assert!((features.positions[center][2] + 0.5).abs() < 1e-6);
+        assert_eq!(features.albedo[center], Color(1., 0., 0.));
+
+        // pixels on the edge of the rectangle are as deep as its middle
+        for x in 0..20 {
+            let depth = features.depth[10 * 20 + x];
+            assert!(depth == 0. || (depth - 1.5).abs() < 0.05, "{x}: {depth}");
+        }
+
+        // unique to the process, test runs can overlap
+        let path =
+            std::env::temp_dir().join(format!("raytracer_aov_test_{}.png", std::process::id()));
+        let written = features.write(&path, &[1; 400]).unwrap();
+        let depth = std::fs::read(&written[0]).unwrap();
+        for path in written {
+            std::fs::remove_file(path).unwrap();
+        }
+
+        let header = b"Pf\n20 20\n-1.0\n";
+        assert!(depth.starts_with(header));
+        assert_eq!(depth.len(), header.len() + 400 * 4);
+    }
+}
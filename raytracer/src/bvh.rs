@@ -0,0 +1,294 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    ray::Ray,
+    sampler::Sampler,
+};
+
+/// relative cost of visiting a node compared to intersecting an object
+const TRAVERSAL_COST: f64 = 0.125;
+
+enum Node {
+    Leaf {
+        bbox: Aabb,
+        start: usize,
+        end: usize,
+    },
+    Interior {
+        bbox: Aabb,
+        left: usize,
+        right: usize,
+        axis: usize,
+    },
+}
+
+/// Bounding volume hierarchy, built with the surface area heuristic (SAH)
+pub struct Bvh<H> {
+    objects: Vec<H>,
+    /// objects without a bounding box (e.g. infinite planes), tested for every ray
+    unbounded: Vec<usize>,
+    /// indices into `objects`, ordered so that every leaf covers a contiguous range
+    indices: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+impl<H: Hittable> Bvh<H> {
+    pub fn new(objects: Vec<H>) -> Self {
+        let mut bounded = Vec::with_capacity(objects.len());
+        let mut unbounded = Vec::new();
+
+        for (i, object) in objects.iter().enumerate() {
+            match object.bounding_box() {
+                Some(bbox) => bounded.push((i, bbox)),
+                None => unbounded.push(i),
+            }
+        }
+
+        let mut bvh = Self {
+            objects,
+            unbounded,
+            indices: Vec::with_capacity(bounded.len()),
+            nodes: Vec::new(),
+        };
+
+        if !bounded.is_empty() {
+            bvh.build(&mut bounded);
+        }
+
+        bvh
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn objects(&self) -> &[H] {
+        &self.objects
+    }
+
+    /// recursively build the subtree over `items`, returning the index of its root node
+    fn build(&mut self, items: &mut [(usize, Aabb)]) -> usize {
+        let bbox = items
+            .iter()
+            .skip(1)
+            .fold(items[0].1, |bbox, (_, b)| bbox.surrounding(b));
+        let node = self.nodes.len();
+
+        match split(items, &bbox) {
+            Some((axis, mid)) => {
+                // reserve the slot, the children are appended after it
+                self.nodes.push(Node::Leaf {
+                    bbox,
+                    start: 0,
+                    end: 0,
+                });
+
+                let (left_items, right_items) = items.split_at_mut(mid);
+                let left = self.build(left_items);
+                let right = self.build(right_items);
+
+                self.nodes[node] = Node::Interior {
+                    bbox,
+                    left,
+                    right,
+                    axis,
+                };
+            }
+            None => {
+                let start = self.indices.len();
+                self.indices.extend(items.iter().map(|(i, _)| i));
+                self.nodes.push(Node::Leaf {
+                    bbox,
+                    start,
+                    end: self.indices.len(),
+                });
+            }
+        }
+
+        node
+    }
+}
+
+/// Find the cheapest split according to the SAH.
+///
+/// Returns the split axis and the number of items that go to the left child,
+/// with `items` sorted along that axis, or `None` if a leaf is cheaper.
+fn split(items: &mut [(usize, Aabb)], bbox: &Aabb) -> Option<(usize, usize)> {
+    let n = items.len();
+    if n <= 2 {
+        return None;
+    }
+
+    let mut best: Option<(usize, usize, f64)> = None;
+    let mut right_area = vec![0.; n];
+
+    for axis in 0..3 {
+        sort_by_centroid(items, axis);
+
+        // sweep from the right to get the areas of all suffixes
+        let mut right = items[n - 1].1;
+        for i in (1..n).rev() {
+            right = right.surrounding(&items[i].1);
+            right_area[i] = right.surface_area();
+        }
+
+        // sweep from the left and evaluate every split position
+        let mut left = items[0].1;
+        for i in 1..n {
+            let cost = i as f64 * left.surface_area() + (n - i) as f64 * right_area[i];
+            if best.is_none_or(|(_, _, c)| cost < c) {
+                best = Some((axis, i, cost));
+            }
+            left = left.surrounding(&items[i].1);
+        }
+    }
+
+    let (axis, mid, cost) = best?;
+    if TRAVERSAL_COST + cost / bbox.surface_area() >= n as f64 {
+        return None;
+    }
+
+    sort_by_centroid(items, axis);
+    Some((axis, mid))
+}
+
+fn sort_by_centroid(items: &mut [(usize, Aabb)], axis: usize) {
+    items.sort_by(|a, b| a.1.centroid()[axis].total_cmp(&b.1.centroid()[axis]));
+}
+
+impl<H: Hittable> Bvh<H> {
+    /// closest hit, together with the index of the object in `objects()`
+    pub fn hit_object(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(usize, HitRecord<'_>)> {
+        self.closest(ray, t_min, t_max, |object, t_closest| {
+            object.hit(ray, t_min, t_closest)
+        })
+    }
+
+    /// closest of the hits `hit` finds on the objects whose boxes the ray
+    /// enters before the closest hit so far
+    fn closest<'a>(
+        &'a self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        mut hit: impl FnMut(&'a H, f64) -> Option<HitRecord<'a>>,
+    ) -> Option<(usize, HitRecord<'a>)> {
+        let mut hit_record = None;
+        let mut t_closest = t_max;
+
+        for &i in &self.unbounded {
+            if let Some(record) = hit(&self.objects[i], t_closest) {
+                t_closest = record.t;
+                hit_record = Some((i, record));
+            }
+        }
+
+        if self.nodes.is_empty() {
+            return hit_record;
+        }
+
+        let mut stack = Vec::with_capacity(32);
+        stack.push(0);
+
+        while let Some(node) = stack.pop() {
+            match self.nodes[node] {
+                Node::Leaf { bbox, start, end } => {
+                    if !bbox.hit(ray, t_min, t_closest) {
+                        continue;
+                    }
+
+                    for &i in &self.indices[start..end] {
+                        if let Some(record) = hit(&self.objects[i], t_closest) {
+                            t_closest = record.t;
+                            hit_record = Some((i, record));
+                        }
+                    }
+                }
+                Node::Interior {
+                    bbox,
+                    left,
+                    right,
+                    axis,
+                } => {
+                    if !bbox.hit(ray, t_min, t_closest) {
+                        continue;
+                    }
+
+                    // visit the child closest to the ray origin first
+                    if ray.direction[axis] < 0. {
+                        stack.push(left);
+                        stack.push(right);
+                    } else {
+                        stack.push(right);
+                        stack.push(left);
+                    }
+                }
+            }
+        }
+
+        hit_record
+    }
+}
+
+impl<H: Hittable> Hittable for Bvh<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.hit_object(ray, t_min, t_max).map(|(_, hit)| hit)
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.closest(ray, t_min, t_max, |object, t_closest| {
+            object.hit_sampled(ray, t_min, t_closest, sampler)
+        })
+        .map(|(_, hit)| hit)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+
+        match self.nodes.first()? {
+            Node::Leaf { bbox, .. } | Node::Interior { bbox, .. } => Some(*bbox),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{point3d::Point3D, scene::random_world};
+
+    #[test]
+    fn matches_linear_search() {
+        let world = random_world(0);
+        let bvh = Bvh::new(world.objects.clone());
+
+        for i in 0..1000 {
+            let phi = i as f64 * 0.01;
+            let ray = Ray::new(
+                Point3D::new(13., 2., 3.),
+                Point3D::new(-13. + 8. * phi.cos(), -2. + phi.sin(), -3. + 4. * phi.sin()),
+            );
+
+            let linear = world.hit(&ray, 0.001, f64::INFINITY).map(|hit| hit.t);
+
+            assert_eq!(bvh.hit(&ray, 0.001, f64::INFINITY).map(|hit| hit.t), linear);
+
+            // the index belongs to the object that was hit
+            if let Some((i, hit)) = bvh.hit_object(&ray, 0.001, f64::INFINITY) {
+                let object = world.objects[i].hit(&ray, 0.001, f64::INFINITY);
+                assert_eq!(object.map(|hit| hit.t), Some(hit.t));
+            }
+        }
+    }
+}
@@ -1,8 +1,122 @@
+use std::f64::consts::PI;
+
 use crate::{
     point3d::{CrossProduct, Normalize, Point3D},
     ray::Ray,
+    sampler::Sampler,
 };
 
+/// Maps points on the image to the rays that are traced through them
+pub trait CameraModel: Send + Sync {
+    /// ray through the point (`s`, `t`) of the image, both from 0 to 1
+    /// starting in the lower left corner
+    fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray;
+
+    /// Widen (or narrow, below 1) the view by `factor` around its center,
+    /// keeping its vertical extent, for a new aspect ratio
+    fn stretch(&mut self, factor: f64);
+
+    /// when the rays are cast, see `with_shutter`
+    fn shutter_mut(&mut self) -> &mut Shutter;
+
+    /// Keep the shutter open from `time0` to `time1`, so that objects moving in
+    /// that interval are blurred
+    fn with_shutter(mut self, time0: f64, time1: f64) -> Self
+    where
+        Self: Sized,
+    {
+        *self.shutter_mut() = Shutter {
+            open: time0,
+            close: time1,
+        };
+        self
+    }
+}
+
+/// How a camera projects the scene onto the image, for cameras that are
+/// built from a description
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Projection {
+    /// thin lens, see `Camera`
+    #[default]
+    Perspective,
+    /// parallel rays, over `height` world units across the image height
+    Orthographic { height: f64 },
+    /// equidistant fisheye, see `Fisheye`
+    Fisheye,
+    /// the whole sphere of directions, see `Equirectangular`
+    Equirectangular,
+}
+
+/// A 35mm-style lens and sensor, as photographers describe them. Scene units
+/// are taken to be metres.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lens {
+    /// in millimetres
+    pub focal_length: f64,
+    /// in millimetres, 36 for full frame
+    pub sensor_width: f64,
+    /// focal length over the diameter of the aperture, e.g. 2.8
+    pub f_number: f64,
+}
+
+impl Lens {
+    /// vertical field of view in degrees on a sensor of `aspect_ratio`
+    pub fn vfov(&self, aspect_ratio: f64) -> f64 {
+        let sensor_height = self.sensor_width / aspect_ratio;
+        2. * (sensor_height / (2. * self.focal_length))
+            .atan()
+            .to_degrees()
+    }
+
+    /// diameter of the aperture in scene units
+    pub fn aperture(&self) -> f64 {
+        self.focal_length / self.f_number / 1000.
+    }
+
+    /// exposure value at ISO 100 of an `exposure_time` in seconds at `iso`
+    pub fn ev100(&self, exposure_time: f64, iso: f64) -> f64 {
+        (self.f_number * self.f_number / exposure_time * 100. / iso).log2()
+    }
+
+    /// Exposure in stops for `DisplayTransform::exposure`, such that radiance
+    /// in cd/m² is exposed as by a camera with saturation-based ISO speed:
+    /// scaled by 1 / (1.2 · 2^EV100)
+    pub fn exposure(&self, exposure_time: f64, iso: f64) -> f32 {
+        (-self.ev100(exposure_time, iso) - 1.2f64.log2()) as f32
+    }
+}
+
+/// times at which the shutter opens and closes
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Shutter {
+    open: f64,
+    close: f64,
+}
+
+impl Shutter {
+    /// moment at which a ray is cast
+    fn sample(&self, sampler: &mut dyn Sampler) -> f64 {
+        if self.close > self.open {
+            self.open + sampler.get_1d() * (self.close - self.open)
+        } else {
+            self.open
+        }
+    }
+}
+
+/// orthonormal basis of a camera at `look_from` looking at `look_at`: right,
+/// up and backwards
+fn basis(look_from: Point3D, look_at: Point3D, vup: Point3D) -> (Point3D, Point3D, Point3D) {
+    let w = (look_from - look_at).normalize();
+    let u = vup.cross(&w).normalize();
+    let v = w.cross(&u);
+
+    (u, v, w)
+}
+
+/// Perspective camera with a thin lens, so that only objects at the focus
+/// distance are sharp
 pub struct Camera {
     origin: Point3D,
     lower_left_corner: Point3D,
@@ -14,6 +128,7 @@ pub struct Camera {
     v: Point3D,
     // w: Point3D,
     lens_radius: f64,
+    shutter: Shutter,
 }
 
 fn degrees_to_radians(vfov: f64) -> f64 {
@@ -35,10 +150,7 @@ impl Camera {
         let viewport_height = 2. * h;
         let viewport_width = aspect_ratio * viewport_height;
 
-        // construct orthonormal basis
-        let w = (look_from - look_at).normalize();
-        let u = vup.cross(&w).normalize();
-        let v = w.cross(&u);
+        let (u, v, w) = basis(look_from, look_at, vup);
 
         let horizontal = focus_distance * viewport_width * u;
         let vertical = focus_distance * viewport_height * v;
@@ -53,16 +165,273 @@ impl Camera {
             v,
             // w,
             lens_radius: aperture / 2.,
+            shutter: Shutter::default(),
         }
     }
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let random_disk = self.lens_radius * Point3D::random_in_unit_disk();
+    /// camera with the field of view and aperture of the `lens`, on a sensor of
+    /// `aspect_ratio`
+    pub fn from_lens(
+        look_from: Point3D,
+        look_at: Point3D,
+        vup: Point3D,
+        lens: Lens,
+        aspect_ratio: f64,
+        focus_distance: f64,
+    ) -> Self {
+        Self::new(
+            look_from,
+            look_at,
+            vup,
+            lens.vfov(aspect_ratio),
+            aspect_ratio,
+            lens.aperture(),
+            focus_distance,
+        )
+    }
+}
+
+impl CameraModel for Camera {
+    fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        let random_disk = self.lens_radius * Point3D::random_in_unit_disk(sampler);
         let offset = self.u * random_disk[0] + self.v * random_disk[1];
+        let time = self.shutter.sample(sampler);
 
         Ray::new(
             self.origin + offset,
             self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
         )
+        .with_time(time)
+    }
+
+    fn stretch(&mut self, factor: f64) {
+        let horizontal = factor * self.horizontal;
+        self.lower_left_corner = self.lower_left_corner + self.horizontal / 2. - horizontal / 2.;
+        self.horizontal = horizontal;
+    }
+
+    fn shutter_mut(&mut self) -> &mut Shutter {
+        &mut self.shutter
+    }
+}
+
+/// Parallel rays from a rectangle around `look_from`, so that sizes do not
+/// shrink with the distance, as in architectural elevations
+pub struct Orthographic {
+    lower_left_corner: Point3D,
+    horizontal: Point3D,
+    vertical: Point3D,
+    direction: Point3D,
+    shutter: Shutter,
+}
+
+impl Orthographic {
+    /// the image covers `height` world units vertically
+    pub fn new(
+        look_from: Point3D,
+        look_at: Point3D,
+        vup: Point3D,
+        height: f64,
+        aspect_ratio: f64,
+    ) -> Self {
+        let (u, v, w) = basis(look_from, look_at, vup);
+        let horizontal = aspect_ratio * height * u;
+        let vertical = height * v;
+
+        Self {
+            lower_left_corner: look_from - horizontal / 2. - vertical / 2.,
+            horizontal,
+            vertical,
+            direction: -w,
+            shutter: Shutter::default(),
+        }
+    }
+}
+
+impl CameraModel for Orthographic {
+    fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        Ray::new(
+            self.lower_left_corner + s * self.horizontal + t * self.vertical,
+            self.direction,
+        )
+        .with_time(self.shutter.sample(sampler))
+    }
+
+    fn stretch(&mut self, factor: f64) {
+        let horizontal = factor * self.horizontal;
+        self.lower_left_corner = self.lower_left_corner + self.horizontal / 2. - horizontal / 2.;
+        self.horizontal = horizontal;
+    }
+
+    fn shutter_mut(&mut self) -> &mut Shutter {
+        &mut self.shutter
+    }
+}
+
+/// Equidistant fisheye: the angle to the view direction grows linearly with
+/// the distance from the image center, up to half of `fov` at the top and
+/// bottom edges. Wider images see further to the sides, up to straight back.
+pub struct Fisheye {
+    origin: Point3D,
+    u: Point3D,
+    v: Point3D,
+    w: Point3D,
+    /// half the field of view across the image height, in radians
+    half_fov: f64,
+    aspect_ratio: f64,
+    shutter: Shutter,
+}
+
+impl Fisheye {
+    /// `fov` degrees across the image height, 180 for a hemisphere
+    pub fn new(
+        look_from: Point3D,
+        look_at: Point3D,
+        vup: Point3D,
+        fov: f64,
+        aspect_ratio: f64,
+    ) -> Self {
+        let (u, v, w) = basis(look_from, look_at, vup);
+
+        Self {
+            origin: look_from,
+            u,
+            v,
+            w,
+            half_fov: degrees_to_radians(fov) / 2.,
+            aspect_ratio,
+            shutter: Shutter::default(),
+        }
+    }
+}
+
+impl CameraModel for Fisheye {
+    fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        // from the center, 1 at the top and bottom edges
+        let x = (2. * s - 1.) * self.aspect_ratio;
+        let y = 2. * t - 1.;
+
+        let theta = (x.hypot(y) * self.half_fov).min(PI);
+        let phi = y.atan2(x);
+        let direction =
+            theta.sin() * (phi.cos() * self.u + phi.sin() * self.v) - theta.cos() * self.w;
+
+        Ray::new(self.origin, direction).with_time(self.shutter.sample(sampler))
+    }
+
+    fn stretch(&mut self, factor: f64) {
+        self.aspect_ratio *= factor;
+    }
+
+    fn shutter_mut(&mut self) -> &mut Shutter {
+        &mut self.shutter
+    }
+}
+
+/// 360° panorama in latitude-longitude layout, as used by environment maps:
+/// the longitude runs from straight back on the left through the view
+/// direction in the middle to straight back on the right, the latitude from
+/// straight down at the bottom to straight up at the top. Images with an
+/// aspect ratio of 2 have square pixels.
+pub struct Equirectangular {
+    origin: Point3D,
+    u: Point3D,
+    v: Point3D,
+    w: Point3D,
+    shutter: Shutter,
+}
+
+impl Equirectangular {
+    pub fn new(look_from: Point3D, look_at: Point3D, vup: Point3D) -> Self {
+        let (u, v, w) = basis(look_from, look_at, vup);
+
+        Self {
+            origin: look_from,
+            u,
+            v,
+            w,
+            shutter: Shutter::default(),
+        }
+    }
+}
+
+impl CameraModel for Equirectangular {
+    fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        let longitude = 2. * PI * (s - 0.5);
+        let latitude = PI * (t - 0.5);
+
+        let direction = latitude.cos() * (longitude.sin() * self.u - longitude.cos() * self.w)
+            + latitude.sin() * self.v;
+
+        Ray::new(self.origin, direction).with_time(self.shutter.sample(sampler))
+    }
+
+    /// always the whole sphere, whatever the aspect ratio
+    fn stretch(&mut self, _factor: f64) {}
+
+    fn shutter_mut(&mut self) -> &mut Shutter {
+        &mut self.shutter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{point3d::Length, sampler::IndependentSampler};
+
+    #[test]
+    fn projections() {
+        let (look_from, look_at) = (Point3D::new(0., 0., 0.), Point3D::new(0., 0., -1.));
+        let up = Point3D::new(0., 1., 0.);
+        let mut sampler = IndependentSampler::new(0);
+        let close = |a: Point3D, b: Point3D| (a.normalize() - b).length() < 1e-9;
+
+        let orthographic = Orthographic::new(look_from, look_at, up, 2., 2.);
+        let ray = orthographic.get_ray(1., 1., &mut sampler);
+        assert!((ray.origin - Point3D::new(2., 1., 0.)).length() < 1e-9);
+        assert!(close(ray.direction, Point3D::new(0., 0., -1.)));
+
+        // the edges of a 180° fisheye look sideways, its corners further back
+        let fisheye = Fisheye::new(look_from, look_at, up, 180., 1.);
+        let forward = fisheye.get_ray(0.5, 0.5, &mut sampler).direction;
+        assert!(close(forward, Point3D::new(0., 0., -1.)));
+        let top = fisheye.get_ray(0.5, 1., &mut sampler).direction;
+        assert!(close(top, Point3D::new(0., 1., 0.)));
+        assert!(fisheye.get_ray(1., 1., &mut sampler).direction[2] > 0.);
+
+        let panorama = Equirectangular::new(look_from, look_at, up);
+        let direction = |s, t| {
+            panorama
+                .get_ray(s, t, &mut IndependentSampler::new(0))
+                .direction
+        };
+        assert!(close(direction(0.5, 0.5), Point3D::new(0., 0., -1.)));
+        assert!(close(direction(0.75, 0.5), Point3D::new(1., 0., 0.)));
+        assert!(close(direction(0., 0.5), Point3D::new(0., 0., 1.)));
+        assert!(close(direction(0.3, 1.), Point3D::new(0., 1., 0.)));
+    }
+
+    #[test]
+    fn lens() {
+        // a normal lens on full frame, wide open
+        let lens = Lens {
+            focal_length: 50.,
+            sensor_width: 36.,
+            f_number: 2.,
+        };
+        assert!((lens.vfov(1.5) - 26.99).abs() < 0.01);
+        assert_eq!(lens.aperture(), 0.025);
+        let (look_from, look_at) = (Point3D::new(0., 0., 0.), Point3D::new(0., 0., -1.));
+        let camera = Camera::from_lens(look_from, look_at, Point3D::new(0., 1., 0.), lens, 1.5, 1.);
+        assert_eq!(camera.lens_radius, 0.0125);
+
+        // sunny 16: f/16 at 1/100 s and ISO 100
+        let lens = Lens {
+            f_number: 16.,
+            ..lens
+        };
+        assert!((lens.ev100(0.01, 100.) - 14.64).abs() < 0.01);
+        // half the time at twice the sensitivity gives the same image
+        assert!((lens.ev100(0.005, 200.) - lens.ev100(0.01, 100.)).abs() < 1e-12);
     }
 }
@@ -1,26 +1,15 @@
 use std::ops::{Add, AddAssign, Div, Mul, Sub};
 
-use rand::Rng;
+use rand::{Rng, RngCore};
+
+use crate::display::DisplayTransform;
 
 /// RGB support only
 ///
-/// clamping to the [0-255] range
-/// any value over 255 is 255, and any value below 0 is 0
+/// linear radiance, unbounded until it is converted for display
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Color(pub f32, pub f32, pub f32);
 
-fn clamp(value: f32) -> f32 {
-    // if value > 255. {
-    //     return 255.;
-    if value > 1. {
-        return 1.;
-    } else if value < 0. {
-        return 0.;
-    }
-
-    value
-}
-
 impl Color {
     pub const fn white() -> Self {
         Self(1., 1., 1.)
@@ -30,13 +19,11 @@ impl Color {
         Self(0., 0., 0.)
     }
 
-    pub fn random() -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn random(rng: &mut dyn RngCore) -> Self {
         Self(rng.gen(), rng.gen(), rng.gen())
     }
 
-    pub fn random_range(min: f32, max: f32) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn random_range(min: f32, max: f32, rng: &mut dyn RngCore) -> Self {
         Self(
             rng.gen_range(min..max),
             rng.gen_range(min..max),
@@ -44,18 +31,19 @@ impl Color {
         )
     }
 
-    fn sqrt(&self) -> Self {
-        Self(self.0.sqrt(), self.1.sqrt(), self.2.sqrt())
+    /// perceived brightness, with the Rec. 709 weights
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.0 + 0.7152 * self.1 + 0.0722 * self.2
     }
 
-    pub fn to_bytes(self, samples: usize) -> [u8; 3] {
-        // sqrt is for gamma correction (= 2)
-        let scaled = (self / samples as f32).sqrt();
+    /// 8-bit display color of the average of `samples` accumulated samples
+    pub fn to_bytes(self, samples: usize, display: &DisplayTransform) -> [u8; 3] {
+        let display = display.apply(self / samples as f32);
 
         [
-            (clamp(scaled.0) * 255.) as u8,
-            (clamp(scaled.1) * 255.) as u8,
-            (clamp(scaled.2) * 255.) as u8,
+            (display.0 * 255.) as u8,
+            (display.1 * 255.) as u8,
+            (display.2 * 255.) as u8,
         ]
     }
 }
@@ -89,6 +77,14 @@ impl Div<f32> for Color {
     }
 }
 
+impl Div<Color> for Color {
+    type Output = Self;
+
+    fn div(self, rhs: Color) -> Self::Output {
+        Self(self.0 / rhs.0, self.1 / rhs.1, self.2 / rhs.2)
+    }
+}
+
 impl Mul<Color> for Color {
     type Output = Self;
 
@@ -150,24 +146,33 @@ mod tests {
 
     #[test]
     fn add() {
-        let c1 = Color(0., 0., 255.);
-        let c2 = Color(10., 10., 10.);
+        let c1 = Color(0., 0., 1.);
+        let c2 = Color(0.25, 0.25, 0.25);
 
-        assert_eq!((c1 + c2).to_bytes(1), [10, 10, 255]);
+        assert_eq!(
+            (c1 + c2).to_bytes(1, &DisplayTransform::default()),
+            [127, 127, 255]
+        );
     }
 
     #[test]
     fn sub() {
-        let c1 = Color(100., 100., 255.);
-        let c2 = Color(150., 10., 30.);
+        let c1 = Color(0.25, 0.5, 1.);
+        let c2 = Color(0.5, 0.1, 0.19);
 
-        assert_eq!((c1 - c2).to_bytes(1), [0, 90, 225]);
+        assert_eq!(
+            (c1 - c2).to_bytes(1, &DisplayTransform::default()),
+            [0, 161, 229]
+        );
     }
 
     #[test]
     fn mul() {
-        let c = Color(11., 19., 234.);
+        let c = Color(0.25, 0.49, 0.81);
 
-        assert_eq!((1.3 * c).to_bytes(1), [14, 25, 255]);
+        assert_eq!(
+            (1.3 * c).to_bytes(1, &DisplayTransform::default()),
+            [145, 203, 255]
+        );
     }
 }
@@ -0,0 +1,122 @@
+use crate::{
+    aabb::Aabb,
+    aarect::{XyRect, XzRect, YzRect},
+    hittable::{FlipFace, HitRecord, Hittable},
+    hittable_list::HittableList,
+    material::Material,
+    point3d::Point3D,
+    ray::Ray,
+};
+
+/// Axis-aligned box made of six rectangles
+pub struct Cuboid {
+    pub min: Point3D,
+    pub max: Point3D,
+    sides: HittableList,
+}
+
+impl Cuboid {
+    pub fn new(min: Point3D, max: Point3D, material: Material) -> Self {
+        let mut sides = HittableList::new();
+
+        // the rectangles face towards positive axes, so the sides at the minimum
+        // corner are flipped to make all of them face outwards
+        sides.add(XyRect::new(
+            min[0],
+            max[0],
+            min[1],
+            max[1],
+            max[2],
+            material.clone(),
+        ));
+        sides.add(FlipFace(XyRect::new(
+            min[0],
+            max[0],
+            min[1],
+            max[1],
+            min[2],
+            material.clone(),
+        )));
+
+        sides.add(XzRect::new(
+            min[0],
+            max[0],
+            min[2],
+            max[2],
+            max[1],
+            material.clone(),
+        ));
+        sides.add(FlipFace(XzRect::new(
+            min[0],
+            max[0],
+            min[2],
+            max[2],
+            min[1],
+            material.clone(),
+        )));
+
+        sides.add(YzRect::new(
+            min[1],
+            max[1],
+            min[2],
+            max[2],
+            max[0],
+            material.clone(),
+        ));
+        sides.add(FlipFace(YzRect::new(
+            min[1], max[1], min[2], max[2], min[0], material,
+        )));
+
+        Self { min, max, sides }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.sides.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Lambertian};
+
+    #[test]
+    fn front_face() {
+        let cuboid = Cuboid::new(
+            Point3D::new(-1., -1., -1.),
+            Point3D::new(1., 1., 1.),
+            Material::Lambertian(Lambertian::new(Color::white())),
+        );
+
+        // entering through every side from outside
+        for axis in 0..3 {
+            for sign in [-1., 1.] {
+                let mut origin = [0.; 3];
+                origin[axis] = 5. * sign;
+                let origin = Point3D::new(origin[0], origin[1], origin[2]);
+
+                let hit = cuboid
+                    .hit(&Ray::new(origin, -origin), 0.001, f64::INFINITY)
+                    .unwrap();
+                assert!(hit.front_face);
+                assert_eq!(hit.normal[axis], sign);
+
+                let hit = cuboid
+                    .hit(
+                        &Ray::new(Point3D::new(0., 0., 0.), origin),
+                        0.001,
+                        f64::INFINITY,
+                    )
+                    .unwrap();
+                assert!(!hit.front_face);
+                assert_eq!(hit.normal[axis], -sign);
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use rayon::prelude::*;
+
+use crate::{aov::Features, color::Color, framebuffer::Framebuffer, point3d::Length};
+
+/// B3 spline, the smoothing kernel of every à-trous iteration
+const KERNEL: [f32; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+
+/// Edge-avoiding à-trous wavelet filter (Dammertz et al. 2010): repeated
+/// blurs with a growing footprint, where neighbours only count if their
+/// color, normal, albedo and depth are close to those of the pixel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Denoiser {
+    /// number of blurs, the footprint doubles with each
+    pub iterations: u32,
+    /// how different colors may be, halved every iteration
+    pub sigma_color: f32,
+    pub sigma_normal: f32,
+    pub sigma_albedo: f32,
+    /// relative to the depth of the pixel
+    pub sigma_depth: f32,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            sigma_color: 4.,
+            sigma_normal: 0.3,
+            sigma_albedo: 0.1,
+            sigma_depth: 0.1,
+        }
+    }
+}
+
+impl Denoiser {
+    pub fn apply(&self, framebuffer: &Framebuffer, features: &Features) -> Framebuffer {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        assert_eq!((width, height), (features.width, features.height));
+
+        // filter the illumination only, so that textures stay sharp
+        let mut illumination: Vec<Color> = framebuffer
+            .pixels()
+            .iter()
+            .zip(&features.albedo)
+            .map(|(&color, &albedo)| color / demodulate(albedo))
+            .collect();
+
+        for i in 0..self.iterations {
+            let step = 1 << i;
+            let sigma_color = self.sigma_color / (1 << i) as f32;
+
+            let mut filtered = vec![Color::black(); width * height];
+            filtered
+                .par_chunks_mut(width)
+                .enumerate()
+                .for_each(|(y, row)| {
+                    for (x, pixel) in row.iter_mut().enumerate() {
+                        *pixel = self.filter(&illumination, features, x, y, step, sigma_color);
+                    }
+                });
+            illumination = filtered;
+        }
+
+        let mut denoised = Framebuffer::new(width, height);
+        for ((pixel, &light), &albedo) in denoised
+            .pixels_mut()
+            .iter_mut()
+            .zip(&illumination)
+            .zip(&features.albedo)
+        {
+            *pixel = light * demodulate(albedo);
+        }
+
+        denoised
+    }
+
+    /// weighted average of the 5x5 taps `step` pixels apart around (x, y)
+    fn filter(
+        &self,
+        illumination: &[Color],
+        features: &Features,
+        x: usize,
+        y: usize,
+        step: usize,
+        sigma_color: f32,
+    ) -> Color {
+        let width = features.width;
+        let p = y * width + x;
+        let color = compress(illumination[p]);
+
+        let mut sum = Color::black();
+        let mut weights = 0.;
+        for (j, ky) in KERNEL.iter().enumerate() {
+            let qy = y as isize + (j as isize - 2) * step as isize;
+            if qy < 0 || qy >= features.height as isize {
+                continue;
+            }
+
+            for (i, kx) in KERNEL.iter().enumerate() {
+                let qx = x as isize + (i as isize - 2) * step as isize;
+                if qx < 0 || qx >= width as isize {
+                    continue;
+                }
+                let q = qy as usize * width + qx as usize;
+
+                let color_distance = distance_squared(color, compress(illumination[q]));
+                let normal_distance = (features.normals[p] - features.normals[q]).length_squared();
+                let albedo_distance = distance_squared(features.albedo[p], features.albedo[q]);
+                let depth_distance = (features.depth[p] - features.depth[q])
+                    / features.depth[p].max(features.depth[q]).max(1e-3);
+
+                let weight = kx
+                    * ky
+                    * (-color_distance / (sigma_color * sigma_color)
+                        - normal_distance as f32 / (self.sigma_normal * self.sigma_normal)
+                        - albedo_distance / (self.sigma_albedo * self.sigma_albedo)
+                        - (depth_distance * depth_distance) as f32
+                            / (self.sigma_depth * self.sigma_depth))
+                        .exp();
+
+                sum += weight * illumination[q];
+                weights += weight;
+            }
+        }
+
+        // the pixel itself always has full weight
+        sum / weights
+    }
+}
+
+/// albedo to divide out of a color, without blowing up black surfaces
+fn demodulate(albedo: Color) -> Color {
+    Color(albedo.0.max(0.01), albedo.1.max(0.01), albedo.2.max(0.01))
+}
+
+/// x / (1 + x), so that fireflies do not dominate the color distance
+fn compress(color: Color) -> Color {
+    Color(
+        color.0 / (1. + color.0),
+        color.1 / (1. + color.1),
+        color.2 / (1. + color.2),
+    )
+}
+
+fn distance_squared(a: Color, b: Color) -> f32 {
+    let d = a - b;
+    d.0 * d.0 + d.1 * d.1 + d.2 * d.2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point3d::Point3D;
+
+    #[test]
+    fn keeps_edges() {
+        // noisy grey on two surfaces that face different ways
+        let mut framebuffer = Framebuffer::new(8, 8);
+        let mut features = Features::new(8, 8);
+        features.albedo.fill(Color::white());
+        features.normals.fill(Point3D::new(0., 1., 0.));
+        features.depth.fill(1.);
+        for (i, pixel) in framebuffer.pixels_mut().iter_mut().enumerate() {
+            let noise = if i % 3 == 0 { 0.2 } else { -0.1 };
+            *pixel = if i % 8 < 4 {
+                Color(0.5 + noise, 0.5 + noise, 0.5 + noise)
+            } else {
+                features.normals[i] = Point3D::new(1., 0., 0.);
+                Color(2., 2., 2.)
+            };
+        }
+
+        let denoised = Denoiser::default().apply(&framebuffer, &features);
+
+        let spread = |x0: usize| {
+            let values = (0..64)
+                .filter(|i| i % 8 >= x0 && i % 8 < x0 + 4)
+                .map(|i| denoised.pixels()[i].0);
+            values.clone().fold(f32::MIN, f32::max) - values.fold(f32::MAX, f32::min)
+        };
+        // the noise is smoothed out, but the bright side does not leak into the grey one
+        assert!(spread(0) < 0.1);
+        assert!(denoised.get(3, 4).0 < 0.6);
+        assert!((denoised.get(4, 4).0 - 2.).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::color::Color;
+
+/// Compresses scene radiance into the displayable [0, 1] range
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum ToneMap {
+    /// cut off everything brighter than 1
+    #[default]
+    Clamp,
+    /// x / (1 + x), never quite reaching white
+    Reinhard,
+    /// Narkowicz' fit of the ACES filmic curve, with a toe and a soft shoulder
+    Aces,
+}
+
+impl ToneMap {
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            ToneMap::Clamp => x,
+            ToneMap::Reinhard => x / (1. + x),
+            ToneMap::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+        }
+        .clamp(0., 1.)
+    }
+}
+
+/// Encoding of the tone mapped values for an 8-bit image
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Transfer {
+    /// store the values as they are
+    Linear,
+    /// square root, a cheap approximation of sRGB
+    #[default]
+    Gamma2,
+    /// the sRGB opto-electronic transfer function
+    Srgb,
+}
+
+impl Transfer {
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            Transfer::Linear => x,
+            Transfer::Gamma2 => x.sqrt(),
+            Transfer::Srgb => {
+                if x <= 0.0031308 {
+                    12.92 * x
+                } else {
+                    1.055 * x.powf(1. / 2.4) - 0.055
+                }
+            }
+        }
+    }
+}
+
+/// Turns linear radiance into display values: exposure, then tone mapping,
+/// then the transfer function
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct DisplayTransform {
+    /// in stops, every stop doubles the brightness
+    pub exposure: f32,
+    pub tone_map: ToneMap,
+    pub transfer: Transfer,
+}
+
+impl DisplayTransform {
+    /// display value of every channel, in [0, 1]
+    pub fn apply(&self, color: Color) -> Color {
+        let scale = self.exposure.exp2();
+        let channel = |x: f32| self.transfer.apply(self.tone_map.apply(scale * x));
+
+        Color(channel(color.0), channel(color.1), channel(color.2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_transform() {
+        let mut display = DisplayTransform {
+            exposure: 1.,
+            tone_map: ToneMap::Reinhard,
+            transfer: Transfer::Linear,
+        };
+        assert_eq!(display.apply(Color(0.5, 1.5, 0.)), Color(0.5, 0.75, 0.));
+
+        display.exposure = 0.;
+        display.tone_map = ToneMap::Clamp;
+        display.transfer = Transfer::Srgb;
+        let srgb = display.apply(Color(0., 0.18, 2.));
+        assert_eq!(srgb.0, 0.);
+        assert!((srgb.1 - 0.4614).abs() < 1e-4);
+        assert!((srgb.2 - 1.).abs() < 1e-6);
+
+        // the filmic curve keeps black black and rolls bright values off towards white
+        display.tone_map = ToneMap::Aces;
+        display.transfer = Transfer::Linear;
+        let aces = display.apply(Color(0., 1., 100.));
+        assert_eq!(aces.0, 0.);
+        assert!(aces.1 > 0.75 && aces.1 < 0.85);
+        assert!(aces.2 > 0.99);
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use image::{codecs::hdr::HdrEncoder, ImageResult, Rgb, Rgb32FImage, RgbImage};
+
+use crate::{color::Color, display::DisplayTransform};
+
+/// Linear RGB radiance per pixel, row by row from the top left
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Framebuffer {
+    /// # Panics
+    ///
+    /// Panics if `width` or `height` is zero
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "a framebuffer needs at least one pixel"
+        );
+
+        Self {
+            width,
+            height,
+            pixels: vec![Color::black(); width * height],
+        }
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.pixels
+    }
+
+    /// Write the image, in a format chosen by the file extension.
+    ///
+    /// `.exr` and `.hdr` keep the unclamped linear radiance, any other format
+    /// supported by the `image` crate gets 8-bit colors through `display`.
+    pub fn write<P: AsRef<Path>>(&self, path: P, display: &DisplayTransform) -> ImageResult<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("exr") => {
+                let data = self.pixels.iter().flat_map(|c| [c.0, c.1, c.2]).collect();
+                Rgb32FImage::from_raw(self.width as u32, self.height as u32, data)
+                    .expect("framebuffer size matches its dimensions")
+                    .save(path)
+            }
+            Some("hdr") => {
+                let data: Vec<Rgb<f32>> =
+                    self.pixels.iter().map(|c| Rgb([c.0, c.1, c.2])).collect();
+                HdrEncoder::new(BufWriter::new(File::create(path)?)).encode(
+                    &data,
+                    self.width,
+                    self.height,
+                )
+            }
+            _ => {
+                let data = self
+                    .pixels
+                    .iter()
+                    .flat_map(|c| c.to_bytes(1, display))
+                    .collect();
+                RgbImage::from_raw(self.width as u32, self.height as u32, data)
+                    .expect("framebuffer size matches its dimensions")
+                    .save(path)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_dynamic_range() {
+        let mut framebuffer = Framebuffer::new(2, 1);
+        framebuffer.pixels_mut()[1] = Color(4., 0.5, 0.);
+
+        // unique to the process, test runs can overlap
+        let path = std::env::temp_dir().join(format!(
+            "raytracer_framebuffer_test_{}.exr",
+            std::process::id()
+        ));
+        framebuffer
+            .write(&path, &DisplayTransform::default())
+            .unwrap();
+        let image = image::open(&path).unwrap().into_rgb32f();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.get_pixel(1, 0).0, [4., 0.5, 0.]);
+        assert_eq!(framebuffer.get(1, 0), Color(4., 0.5, 0.));
+    }
+}
@@ -1,13 +1,85 @@
-use crate::{material::Material, point3d::Point3D, ray::Ray};
+use std::sync::Arc;
 
-pub struct HitRecord {
+use crate::{aabb::Aabb, material::Material, point3d::Point3D, ray::Ray, sampler::Sampler};
+
+/// Conceptually, an "infinitesimaly small" real number
+pub(crate) const EPSILON: f64 = 0.001;
+
+pub struct HitRecord<'a> {
     pub point: Point3D,
     pub normal: Point3D,
     pub t: f64,
-    pub material: Material,
+    /// surface coordinates of the hit point
+    pub u: f64,
+    pub v: f64,
+    pub material: &'a Material,
     pub front_face: bool,
 }
 
 pub trait Hittable {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+
+    /// Like `hit`, but objects that scatter at a random distance inside them,
+    /// like participating media, draw that distance from the `sampler`
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.hit(ray, t_min, t_max)
+    }
+
+    /// box enclosing the object, `None` if the object is unbounded
+    fn bounding_box(&self) -> Option<Aabb>;
+}
+
+impl<H: Hittable + ?Sized> Hittable for Arc<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        (**self).hit(ray, t_min, t_max)
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        (**self).hit_sampled(ray, t_min, t_max, sampler)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        (**self).bounding_box()
+    }
+}
+
+/// Swaps which side of a surface counts as its front face
+pub struct FlipFace<H>(pub H);
+
+impl<H: Hittable> Hittable for FlipFace<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut hit = self.0.hit(ray, t_min, t_max)?;
+        hit.front_face = !hit.front_face;
+
+        Some(hit)
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        let mut hit = self.0.hit_sampled(ray, t_min, t_max, sampler)?;
+        hit.front_face = !hit.front_face;
+
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.0.bounding_box()
+    }
 }
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    ray::Ray,
+    sampler::Sampler,
+};
+
+/// Shared handle to any kind of object that can be placed in a scene
+pub type SharedHittable = Arc<dyn Hittable + Send + Sync>;
+
+/// Heterogeneous collection of objects, searched linearly
+#[derive(Clone, Default)]
+pub struct HittableList {
+    pub objects: Vec<SharedHittable>,
+}
+
+impl HittableList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<H: Hittable + Send + Sync + 'static>(&mut self, object: H) {
+        self.objects.push(Arc::new(object));
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// closest of the hits `hit` finds on each object before the closest so far
+    fn closest<'a>(
+        &'a self,
+        t_max: f64,
+        mut hit: impl FnMut(&'a SharedHittable, f64) -> Option<HitRecord<'a>>,
+    ) -> Option<HitRecord<'a>> {
+        let mut hit_record = None;
+        let mut t_closest = t_max;
+
+        for object in &self.objects {
+            if let Some(record) = hit(object, t_closest) {
+                t_closest = record.t;
+                hit_record = Some(record);
+            }
+        }
+
+        hit_record
+    }
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.closest(t_max, |object, t_closest| object.hit(ray, t_min, t_closest))
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.closest(t_max, |object, t_closest| {
+            object.hit_sampled(ray, t_min, t_closest, sampler)
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut objects = self.objects.iter();
+        let first = objects.next()?.bounding_box()?;
+
+        objects.try_fold(first, |bbox, object| {
+            Some(bbox.surrounding(&object.bounding_box()?))
+        })
+    }
+}
@@ -0,0 +1,369 @@
+use crate::{
+    color::Color,
+    hittable::{HitRecord, Hittable, EPSILON},
+    light::compute_lighting,
+    material::{reflect, reflectance, refract, Material, Scatterable},
+    point3d::{CrossProduct, DotProduct, Length, Normalize, Point3D},
+    ray::Ray,
+    sampler::Sampler,
+    scene::Scene,
+};
+
+/// Light transport algorithm: how much light arrives at the camera along a ray
+pub trait Integrator: Send + Sync {
+    fn radiance(&self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> Color;
+}
+
+/// Monte Carlo path tracing of the materials, lit by emitters and the background
+pub struct PathTracer;
+
+impl PathTracer {
+    fn ray_color(ray: &Ray, scene: &Scene, depth: isize, sampler: &mut dyn Sampler) -> Color {
+        if depth <= 0 {
+            return Color::black();
+        }
+
+        if let Some(hit_record) = scene
+            .objects
+            .hit_sampled(ray, EPSILON, f64::INFINITY, sampler)
+        {
+            let emitted = hit_record.material.emitted(&hit_record);
+
+            if let Some((scattered, attenuation)) =
+                hit_record.material.scatter(ray, &hit_record, sampler)
+            {
+                return emitted
+                    + attenuation * Self::ray_color(&scattered, scene, depth - 1, sampler);
+            }
+
+            return emitted;
+        }
+
+        scene.background.color(ray)
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> Color {
+        Self::ray_color(ray, scene, scene.depth_max, sampler)
+    }
+}
+
+/// Recursive ray tracing as in Computer Graphics from Scratch: local lighting from
+/// `Scene::lights`, plus perfect mirror reflection weighted by the material's
+/// `reflective` coefficient and Fresnel-weighted reflection and refraction
+/// for dielectrics. Noise-free, so a few samples per pixel suffice.
+pub struct Whitted;
+
+impl Whitted {
+    fn trace(ray: &Ray, scene: &Scene, depth: isize) -> Color {
+        let hit_record = match scene.objects.hit(ray, EPSILON, f64::INFINITY) {
+            Some(hit_record) => hit_record,
+            None => return scene.background.color(ray),
+        };
+        let material = hit_record.material;
+        let direction = ray.direction.normalize();
+
+        if let Material::Dielectric(dielectric) = material {
+            // with no depth left, see through the glass to the background
+            return if depth > 0 {
+                Self::refraction(&hit_record, ray, dielectric.refraction_index, scene, depth)
+            } else {
+                scene.background.color(ray)
+            };
+        }
+
+        let intensity = compute_lighting(
+            &scene.lights,
+            &scene.objects,
+            hit_record.point,
+            hit_record.normal,
+            -ray.direction,
+            material.specular(),
+            ray.time,
+        );
+        let local_color =
+            material.emitted(&hit_record) + intensity as f32 * material.albedo(&hit_record);
+
+        let reflective = material.reflective(&hit_record);
+        if depth <= 0 || reflective == Color::black() {
+            return local_color;
+        }
+
+        let reflected =
+            Ray::new(hit_record.point, reflect(direction, hit_record.normal)).with_time(ray.time);
+        let reflected_color = Self::trace(&reflected, scene, depth - 1);
+
+        (Color::white() - reflective) * local_color + reflective * reflected_color
+    }
+
+    /// deterministic version of `Dielectric::scatter`, following both rays
+    fn refraction(
+        hit_record: &HitRecord,
+        ray: &Ray,
+        refraction_index: f64,
+        scene: &Scene,
+        depth: isize,
+    ) -> Color {
+        let direction = ray.direction.normalize();
+        let refraction_ratio = if hit_record.front_face {
+            1. / refraction_index
+        } else {
+            refraction_index
+        };
+
+        let cos_theta = (-direction).dot(&hit_record.normal).min(1.);
+        let sin_theta = (1. - cos_theta * cos_theta).sqrt();
+
+        let reflected =
+            Ray::new(hit_record.point, reflect(direction, hit_record.normal)).with_time(ray.time);
+        let reflected_color = Self::trace(&reflected, scene, depth - 1);
+
+        // total internal reflection
+        if refraction_ratio * sin_theta > 1. {
+            return reflected_color;
+        }
+
+        let refracted = Ray::new(
+            hit_record.point,
+            refract(direction, hit_record.normal, refraction_ratio),
+        )
+        .with_time(ray.time);
+        let refracted_color = Self::trace(&refracted, scene, depth - 1);
+        let r = reflectance(cos_theta, refraction_ratio) as f32;
+
+        r * reflected_color + (1. - r) * refracted_color
+    }
+}
+
+impl Integrator for Whitted {
+    // deterministic, nothing to sample
+    fn radiance(&self, ray: &Ray, scene: &Scene, _sampler: &mut dyn Sampler) -> Color {
+        Self::trace(ray, scene, scene.depth_max)
+    }
+}
+
+/// Fraction of the hemisphere above the first hit that is not blocked by
+/// objects within `distance`, as shades of grey
+pub struct AmbientOcclusion {
+    pub samples: usize,
+    pub distance: f64,
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> Color {
+        let hit_record = match scene
+            .objects
+            .hit_sampled(ray, EPSILON, f64::INFINITY, sampler)
+        {
+            Some(hit_record) => hit_record,
+            None => return Color::white(),
+        };
+
+        let unoccluded = (0..self.samples)
+            .filter(|_| {
+                let direction = random_cosine_direction(hit_record.normal, sampler);
+                scene
+                    .objects
+                    .hit_sampled(
+                        &Ray::new(hit_record.point, direction).with_time(ray.time),
+                        EPSILON,
+                        self.distance,
+                        sampler,
+                    )
+                    .is_none()
+            })
+            .count();
+
+        Color::white() * (unoccluded as f32 / self.samples.max(1) as f32)
+    }
+}
+
+/// random unit vector in the hemisphere around `normal`, with probability
+/// proportional to the cosine of the angle to the normal
+fn random_cosine_direction(normal: Point3D, sampler: &mut dyn Sampler) -> Point3D {
+    let (r1, r2) = sampler.get_2d();
+
+    let phi = 2. * std::f64::consts::PI * r1;
+    let x = phi.cos() * r2.sqrt();
+    let y = phi.sin() * r2.sqrt();
+    let z = (1. - r2).sqrt();
+
+    // orthonormal basis around the normal
+    let helper = if normal[0].abs() > 0.9 {
+        Point3D::new(0., 1., 0.)
+    } else {
+        Point3D::new(1., 0., 0.)
+    };
+    let t = normal.cross(&helper).normalize();
+    let b = normal.cross(&t);
+
+    x * t + y * b + z * normal
+}
+
+#[derive(Clone, Copy)]
+pub enum DebugMode {
+    /// world-space normal of the first hit, mapped from [-1, 1] to [0, 1]
+    Normals,
+    /// distance to the first hit, white up close fading to black at `far`
+    Depth { far: f64 },
+    /// white where a ray hits an object, black where it does not
+    HitMiss,
+}
+
+/// Visualizes properties of the first hit instead of simulating light
+pub struct DebugView {
+    pub mode: DebugMode,
+}
+
+impl Integrator for DebugView {
+    fn radiance(&self, ray: &Ray, scene: &Scene, _sampler: &mut dyn Sampler) -> Color {
+        let hit_record = match scene.objects.hit(ray, EPSILON, f64::INFINITY) {
+            Some(hit_record) => hit_record,
+            None => return Color::black(),
+        };
+
+        match self.mode {
+            DebugMode::Normals => {
+                let n = hit_record.normal;
+                Color(
+                    0.5 * (n[0] as f32 + 1.),
+                    0.5 * (n[1] as f32 + 1.),
+                    0.5 * (n[2] as f32 + 1.),
+                )
+            }
+            DebugMode::Depth { far } => {
+                let distance = hit_record.t * ray.direction.length();
+                Color::white() * (1. - distance / far).clamp(0., 1.) as f32
+            }
+            DebugMode::HitMiss => Color::white(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sampler::IndependentSampler, scene::Background};
+
+    const SCENE: &str = r#"
+background = [0.2, 0.4, 0.6]
+
+[image]
+width = 10
+height = 10
+samples_per_pixel = 1
+max_depth = 3
+
+[camera]
+look_from = [0.0, 1.0, 0.0]
+look_at = [0.0, 0.0, 0.0]
+vup = [0.0, 0.0, 1.0]
+vfov = 90.0
+
+[materials.mirror]
+type = "lambertian"
+albedo = [1.0, 1.0, 1.0]
+reflective = 1.0
+
+[[objects]]
+type = "plane"
+point = [0.0, 0.0, 0.0]
+normal = [0.0, 1.0, 0.0]
+material = "mirror"
+"#;
+
+    #[test]
+    fn whitted_mirror() {
+        let scene = Scene::parse(SCENE).unwrap();
+        let ray = Ray::new(Point3D::new(0., 1., 0.), Point3D::new(0.3, -1., 0.2));
+
+        let mut sampler = IndependentSampler::new(0);
+
+        // a perfect mirror with no lights shows the background
+        assert_eq!(
+            Whitted.radiance(&ray, &scene, &mut sampler),
+            Color(0.2, 0.4, 0.6)
+        );
+
+        // as does the mirror of the deprecated direct lighting
+        let source = format!("shading = \"direct_lighting\"\n{SCENE}");
+        let scene = Scene::parse(&source).unwrap();
+        assert_eq!(
+            scene.integrator.radiance(&ray, &scene, &mut sampler),
+            Color(0.2, 0.4, 0.6)
+        );
+
+        // and glass that cannot be followed any further
+        let source = SCENE.replace("max_depth = 3", "max_depth = 0").replace(
+            "type = \"lambertian\"\nalbedo = [1.0, 1.0, 1.0]\nreflective = 1.0",
+            "type = \"dielectric\"\nrefraction_index = 1.5",
+        );
+        let scene = Scene::parse(&source).unwrap();
+        assert_eq!(
+            Whitted.radiance(&ray, &scene, &mut sampler),
+            Color(0.2, 0.4, 0.6)
+        );
+    }
+
+    #[test]
+    fn emission_and_background() {
+        // a light under the camera, and the sky everywhere else
+        let source = SCENE.replace("background = [0.2, 0.4, 0.6]", "").replace(
+            "type = \"lambertian\"\nalbedo = [1.0, 1.0, 1.0]\nreflective = 1.0",
+            "type = \"light\"\nemit = [4.0, 2.0, 1.0]",
+        );
+        let scene = Scene::parse(&source).unwrap();
+        let mut sampler = IndependentSampler::new(0);
+
+        let down = Ray::new(Point3D::new(0., 1., 0.), Point3D::new(0.3, -1., 0.2));
+        assert_eq!(
+            PathTracer.radiance(&down, &scene, &mut sampler),
+            Color(4., 2., 1.)
+        );
+
+        // the sky is blue straight up, and halfway to white at the horizon
+        let up = Ray::new(Point3D::new(0., 1., 0.), Point3D::new(0., 1., 0.));
+        assert_eq!(
+            PathTracer.radiance(&up, &scene, &mut sampler),
+            Color(0.5, 0.7, 1.)
+        );
+        let horizon = Ray::new(Point3D::new(0., 1., 0.), Point3D::new(1., 0., 0.));
+        let color = Background::Sky.color(&horizon);
+        assert!((color.0 - 0.75).abs() < 1e-6 && color.2 == 1.);
+
+        assert_eq!(
+            Background::Color(Color(0.1, 0.2, 0.3)).color(&up),
+            Color(0.1, 0.2, 0.3)
+        );
+    }
+
+    #[test]
+    fn unoccluded_plane() {
+        let scene = Scene::parse(SCENE).unwrap();
+        let ray = Ray::new(Point3D::new(0., 1., 0.), Point3D::new(0.3, -1., 0.2));
+        let ambient_occlusion = AmbientOcclusion {
+            samples: 8,
+            distance: f64::INFINITY,
+        };
+
+        let mut sampler = IndependentSampler::new(0);
+
+        assert_eq!(
+            ambient_occlusion.radiance(&ray, &scene, &mut sampler),
+            Color::white()
+        );
+
+        let hit_miss = DebugView {
+            mode: DebugMode::HitMiss,
+        };
+        assert_eq!(
+            hit_miss.radiance(&ray, &scene, &mut sampler),
+            Color::white()
+        );
+        assert_eq!(
+            hit_miss.radiance(&Ray::new(ray.origin, -ray.direction), &scene, &mut sampler),
+            Color::black()
+        );
+    }
+}
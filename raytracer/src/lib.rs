@@ -1,10 +1,33 @@
+pub mod aabb;
+pub mod aarect;
+pub mod accumulator;
+pub mod animation;
+pub mod aov;
+pub mod bvh;
 pub mod camera;
 pub mod color;
+pub mod cuboid;
+pub mod denoise;
+pub mod display;
+pub mod framebuffer;
 pub mod hittable;
+pub mod hittable_list;
+pub mod integrator;
 pub mod light;
 pub mod material;
+pub mod matrix;
+pub mod medium;
+pub mod mesh;
+pub mod moving;
+pub mod perlin;
+pub mod plane;
 pub mod point3d;
 pub mod ray;
 pub mod render;
+pub mod sampler;
 pub mod scene;
+pub mod scene_file;
 pub mod sphere;
+pub mod texture;
+pub mod transform;
+pub mod triangle;
@@ -1,37 +1,143 @@
-use crate::point3d::Point3D;
+use crate::{
+    hittable::{Hittable, EPSILON},
+    point3d::{DotProduct, Length, Point3D},
+    ray::Ray,
+};
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy)]
 pub enum LightType {
     Ambient,
     Directional,
     Point,
 }
 
+#[derive(Clone, Copy)]
 pub struct Light {
     pub typ: LightType,
     pub intensity: f64,
+    /// position of a point light, or the direction towards a directional light
     pub point: Option<Point3D>,
 }
 
-// impl Light {
-//     pub fn new(typ: LightType, intensity: f64, point: Option<Point3D>) -> Light {
-//         match typ {
-//             LightType::Ambient => Self {
-//                 typ,
-//                 intensity,
-//                 point: None,
-//             },
-//             _ => {
-//                 if point.is_none() {
-//                     panic!("Directional or Point light must have a direction or origin!");
-//                 }
-
-//                 Self {
-//                     typ,
-//                     intensity,
-//                     point,
-//                 }
-//             }
-//         }
-//     }
-// }
+impl Light {
+    pub fn new(typ: LightType, intensity: f64, point: Option<Point3D>) -> Light {
+        match typ {
+            LightType::Ambient => Self {
+                typ,
+                intensity,
+                point: None,
+            },
+            _ => {
+                if point.is_none() {
+                    panic!("Directional or Point light must have a direction or origin!");
+                }
+
+                Self {
+                    typ,
+                    intensity,
+                    point,
+                }
+            }
+        }
+    }
+
+    pub fn ambient(intensity: f64) -> Self {
+        Self::new(LightType::Ambient, intensity, None)
+    }
+
+    pub fn directional(intensity: f64, direction: Point3D) -> Self {
+        Self::new(LightType::Directional, intensity, Some(direction))
+    }
+
+    pub fn point(intensity: f64, position: Point3D) -> Self {
+        Self::new(LightType::Point, intensity, Some(position))
+    }
+}
+
+/// Total intensity of the light arriving at `point` and reflected towards `view`
+///
+/// Diffuse and (if `specular` is given) Phong specular reflection as in
+/// chapter 3 of Computer Graphics from Scratch, with shadow rays cast
+/// against `world` at the given `time`.
+pub fn compute_lighting(
+    lights: &[Light],
+    world: &dyn Hittable,
+    point: Point3D,
+    normal: Point3D,
+    view: Point3D,
+    specular: Option<f64>,
+    time: f64,
+) -> f64 {
+    let mut intensity = 0.;
+
+    for light in lights {
+        let (direction, t_max) = match (light.typ, light.point) {
+            (LightType::Ambient, _) | (_, None) => {
+                intensity += light.intensity;
+                continue;
+            }
+            (LightType::Point, Some(position)) => (position - point, 1.),
+            (LightType::Directional, Some(direction)) => (direction, f64::INFINITY),
+        };
+
+        // shadow check
+        if world
+            .hit(&Ray::new(point, direction).with_time(time), EPSILON, t_max)
+            .is_some()
+        {
+            continue;
+        }
+
+        // diffuse
+        let n_dot_l = normal.dot(&direction);
+        if n_dot_l > 0. {
+            intensity += light.intensity * n_dot_l / (normal.length() * direction.length());
+        }
+
+        // specular
+        if let Some(s) = specular {
+            let reflected = 2. * normal.dot(&direction) * normal - direction;
+            let r_dot_v = reflected.dot(&view);
+            if r_dot_v > 0. {
+                intensity +=
+                    light.intensity * (r_dot_v / (reflected.length() * view.length())).powf(s);
+            }
+        }
+    }
+
+    intensity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        hittable_list::HittableList,
+        material::{Lambertian, Material},
+        sphere::Sphere,
+    };
+
+    #[test]
+    fn diffuse_and_shadow() {
+        let material = Material::Lambertian(Lambertian::new(Color::white()));
+        let mut world = HittableList::new();
+        world.add(Sphere::new(Point3D::new(0., 2., 0.), 0.5, material));
+
+        let lights = [
+            Light::ambient(0.2),
+            Light::point(0.6, Point3D::new(0., 4., 0.)),
+            Light::directional(0.2, Point3D::new(1., 0., 0.)),
+        ];
+        let up = Point3D::new(0., 1., 0.);
+
+        // the sphere blocks the point light, the directional light grazes the surface
+        let shadowed =
+            compute_lighting(&lights, &world, Point3D::new(0., 0., 0.), up, up, None, 0.);
+        assert!((shadowed - 0.2).abs() < 1e-12);
+
+        // next to the sphere, the point light shines in at 45 degrees
+        let lit = compute_lighting(&lights, &world, Point3D::new(4., 0., 0.), up, up, None, 0.);
+        assert!((lit - (0.2 + 0.6 / 2f64.sqrt())).abs() < 1e-12);
+    }
+}
@@ -1,7 +1,370 @@
-use raytracer::{render::render, scene::Scene};
+use std::{
+    io::Write,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use clap::{builder::RangedU64ValueParser, Parser, ValueEnum};
+use raytracer::{
+    accumulator::Accumulator,
+    animation::frame_path,
+    aov::Features,
+    denoise::Denoiser,
+    display::{DisplayTransform, Transfer},
+    integrator::{AmbientOcclusion, DebugMode, DebugView, Integrator, PathTracer, Whitted},
+    render::{checkpoint_fingerprint, render_progressive, Progress},
+    scene::{Scene, BUILT_IN_SCENES},
+};
+
+/// samples per pixel added by every pass, unless `--pass-samples` says otherwise
+const SAMPLES_PER_PASS: usize = 16;
+
+/// Render a scene with a path tracer, in passes that refine the image
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// TOML scene file, or the name of a built-in scene: random_world
+    #[arg(default_value = "random_world")]
+    scene: String,
+
+    /// image to write, the extension picks the format: `.exr` and `.hdr` keep
+    /// the linear, unclamped radiance
+    #[arg(short, long, default_value = "test.png")]
+    output: PathBuf,
+
+    /// image width in pixels, the height follows unless given as well
+    #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    width: Option<usize>,
+
+    /// image height in pixels, the width follows unless given as well
+    #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    height: Option<usize>,
+
+    /// samples per pixel, instead of the scene's
+    #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    spp: Option<usize>,
+
+    /// bounces per path, instead of the scene's
+    #[arg(long)]
+    max_depth: Option<isize>,
+
+    /// render threads, all cores by default
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// starts the random numbers of every sample
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// how radiance is computed, instead of the scene's integrator
+    #[arg(long, value_enum)]
+    integrator: Option<IntegratorChoice>,
+
+    /// distance at which the depth view fades to black
+    #[arg(long, default_value_t = 20., value_parser = parse_distance)]
+    far: f64,
+
+    /// frames of an animated scene to render, as `FIRST-LAST` or one frame,
+    /// instead of all of them; the frame number is added to every file name
+    #[arg(long, value_parser = parse_frames)]
+    frames: Option<RangeInclusive<u32>>,
+
+    /// samples per pixel added by every pass
+    #[arg(
+        long,
+        default_value_t = SAMPLES_PER_PASS,
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pass_samples: usize,
+
+    /// save the accumulated samples after every pass, and continue from them
+    /// if the file exists
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<PathBuf>,
+
+    /// write the number of samples per pixel as an image
+    #[arg(long, value_name = "FILE")]
+    heatmap: Option<PathBuf>,
+
+    /// smooth the noise of the image
+    #[arg(long)]
+    denoise: bool,
+
+    /// write depth, normals, positions, albedo, object ids and sample counts
+    /// as PFM files next to the image
+    #[arg(long)]
+    aovs: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+#[value(rename_all = "snake_case")]
+enum IntegratorChoice {
+    PathTracing,
+    /// lit by the scene's lights
+    Whitted,
+    AmbientOcclusion,
+    Normals,
+    /// fades to black at `--far`
+    Depth,
+    HitMiss,
+}
+
+impl IntegratorChoice {
+    fn build(self, far: f64) -> Box<dyn Integrator> {
+        match self {
+            IntegratorChoice::PathTracing => Box::new(PathTracer),
+            IntegratorChoice::Whitted => Box::new(Whitted),
+            IntegratorChoice::AmbientOcclusion => Box::new(AmbientOcclusion {
+                samples: 16,
+                distance: f64::INFINITY,
+            }),
+            IntegratorChoice::Normals => Box::new(DebugView {
+                mode: DebugMode::Normals,
+            }),
+            IntegratorChoice::Depth => Box::new(DebugView {
+                mode: DebugMode::Depth { far },
+            }),
+            IntegratorChoice::HitMiss => Box::new(DebugView {
+                mode: DebugMode::HitMiss,
+            }),
+        }
+    }
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let scene = Scene::new();
+    let args = Args::parse();
+
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
+
+    let mut scene = match Scene::built_in(&args.scene) {
+        Some(scene) => scene,
+        None if Path::new(&args.scene).exists() => Scene::from_file(&args.scene)?,
+        None => {
+            return Err(format!(
+                "`{}` is neither a scene file nor a built-in scene ({})",
+                args.scene,
+                BUILT_IN_SCENES.join(", ")
+            )
+            .into())
+        }
+    };
+    scene.resize(args.width, args.height);
+    if let Some(spp) = args.spp {
+        scene.samples_per_pixel = spp;
+    }
+    if let Some(max_depth) = args.max_depth {
+        scene.depth_max = max_depth;
+    }
+    if let Some(integrator) = args.integrator {
+        scene.integrator = integrator.build(args.far);
+    }
+    if args.denoise && scene.denoiser.is_none() {
+        scene.denoiser = Some(Denoiser::default());
+    }
+
+    let Some(animation) = scene.animation.take() else {
+        if args.frames.is_some() {
+            return Err("--frames needs a scene with an [animation]".into());
+        }
+        return render_image(&scene, &args, None);
+    };
+
+    // the scene is set up once, every frame only moves the camera and objects
+    for frame in args.frames.clone().unwrap_or(animation.frames.clone()) {
+        eprintln!("frame {frame}");
+        animation.apply(&mut scene, frame);
+        render_image(&scene, &args, Some(frame))?;
+    }
+
+    Ok(())
+}
+
+/// Render the scene to `--output`, or to its numbered file for a `frame` of
+/// an animation
+fn render_image(
+    scene: &Scene,
+    args: &Args,
+    frame: Option<u32>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let numbered = |path: &Path| match frame {
+        Some(frame) => frame_path(path, frame),
+        None => path.to_path_buf(),
+    };
+    let output = numbered(&args.output);
+    let checkpoint = args.checkpoint.as_deref().map(numbered);
+
+    // samples only add up with the same scene, sampler and settings, but
+    // there may be more of them
+    let source = if BUILT_IN_SCENES.contains(&args.scene.as_str()) {
+        args.scene.clone()
+    } else {
+        std::fs::read_to_string(&args.scene)?
+    };
+    let fingerprint = checkpoint_fingerprint(
+        &source,
+        scene,
+        &format!("{:?} {}", args.integrator, args.far),
+    );
+
+    // continue an interrupted render, or add samples to a finished one
+    let mut accumulator = match &checkpoint {
+        Some(path) if path.exists() => {
+            let accumulator = Accumulator::load(path)?;
+            if (accumulator.width, accumulator.height) != (scene.width, scene.height) {
+                return Err(format!(
+                    "checkpoint `{}` does not match the image size",
+                    path.display()
+                )
+                .into());
+            }
+            if accumulator.fingerprint != fingerprint {
+                return Err(format!(
+                    "checkpoint `{}` belongs to another scene or other settings",
+                    path.display()
+                )
+                .into());
+            }
+            if accumulator.seed != args.seed {
+                return Err(format!(
+                    "checkpoint `{}` was rendered with seed {}, not {}",
+                    path.display(),
+                    accumulator.seed,
+                    args.seed
+                )
+                .into());
+            }
+            accumulator
+        }
+        _ => Accumulator::new(scene.width, scene.height, args.seed).with_fingerprint(fingerprint),
+    };
+
+    // the first hits guide the denoiser and are written as AOVs, they are
+    // cheap and the same for every pass
+    let features = (scene.denoiser.is_some() || args.aovs).then(|| Features::render(scene));
+    let write = |accumulator: &Accumulator| {
+        let framebuffer = accumulator.framebuffer();
+        match (&scene.denoiser, &features) {
+            (Some(denoiser), Some(features)) => denoiser
+                .apply(&framebuffer, features)
+                .write(&output, &scene.display),
+            _ => framebuffer.write(&output, &scene.display),
+        }
+    };
+
+    // a finished checkpoint needs no more passes, but still an image
+    if accumulator.samples_per_pixel() as usize >= scene.samples_per_pixel {
+        write(&accumulator)?;
+    }
+
+    // tiles finish out of order on several threads, only show the latest state
+    let shown = AtomicUsize::new(0);
+    let progress = |progress: &Progress| {
+        if shown.fetch_max(progress.tiles_done, Ordering::Relaxed) < progress.tiles_done {
+            show_progress(progress);
+        }
+    };
+
+    // after every pass, write what there is so far
+    let result = render_progressive(
+        scene,
+        &mut accumulator,
+        args.pass_samples,
+        &progress,
+        |accumulator| -> Result<(), Box<dyn std::error::Error>> {
+            write(accumulator)?;
+            if let Some(path) = &checkpoint {
+                accumulator.save(path)?;
+            }
+
+            Ok(())
+        },
+    );
+    eprintln!();
+    result?;
+
+    // where the samples went, brighter is more
+    if let Some(path) = &args.heatmap {
+        let display = DisplayTransform {
+            transfer: Transfer::Linear,
+            ..Default::default()
+        };
+        accumulator
+            .sample_heatmap()
+            .write(numbered(path), &display)?;
+    }
+
+    if let (true, Some(features)) = (args.aovs, &features) {
+        features.write(&output, accumulator.sample_counts())?;
+    }
+
+    Ok(())
+}
+
+/// `FIRST-LAST`, or a single frame
+fn parse_frames(frames: &str) -> Result<RangeInclusive<u32>, String> {
+    let number = |n: &str| {
+        n.trim()
+            .parse::<u32>()
+            .map_err(|e| format!("`{n}` is not a frame number: {e}"))
+    };
+
+    let (first, last) = match frames.split_once('-') {
+        Some((first, last)) => (number(first)?, number(last)?),
+        None => (number(frames)?, number(frames)?),
+    };
+    if first > last {
+        return Err(format!("frame {first} comes after {last}"));
+    }
+
+    Ok(first..=last)
+}
+
+/// a finite distance greater than 0
+fn parse_distance(distance: &str) -> Result<f64, String> {
+    let distance = distance
+        .parse::<f64>()
+        .map_err(|e| format!("`{distance}` is not a distance: {e}"))?;
+    if !distance.is_finite() || distance <= 0. {
+        return Err("the distance must be finite and greater than 0".to_string());
+    }
+
+    Ok(distance)
+}
+
+/// progress bar with the throughput and remaining time, redrawn on one line
+fn show_progress(progress: &Progress) {
+    const WIDTH: usize = 30;
+    let filled = (progress.fraction() * WIDTH as f64) as usize;
+
+    let mut stderr = std::io::stderr().lock();
+    let _ = write!(
+        stderr,
+        "\r{:3.0}% [{}{}] {:.1}/{:.1} Msamples, {:.2} Msamples/s, {} elapsed, {} left ",
+        (100. * progress.fraction()).floor(),
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        progress.samples_done as f64 / 1e6,
+        progress.samples_total as f64 / 1e6,
+        progress.samples_per_second / 1e6,
+        format_duration(progress.elapsed),
+        format_duration(progress.eta),
+    );
+    let _ = stderr.flush();
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
 
-    render(&scene, "test.png")
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
 }
@@ -1,69 +1,170 @@
+use std::sync::Arc;
+
 use crate::{
     color::Color,
     hittable::HitRecord,
     point3d::{DotProduct, Length, Normalize, Point3D},
     ray::Ray,
+    sampler::Sampler,
+    texture::{SharedTexture, SolidColor},
 };
 
-use rand::Rng;
-
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Material {
     Lambertian(Lambertian),
     Metal(Metal),
     Dielectric(Dielectric),
-    Light,
+    DiffuseLight(DiffuseLight),
+    Isotropic(Isotropic),
 }
 
 pub trait Scatterable {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)>;
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)>;
+
+    /// light given off by the surface itself
+    fn emitted(&self, _hit_record: &HitRecord) -> Color {
+        Color::black()
+    }
 }
 
 impl Scatterable for Material {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        match self {
+            Material::Lambertian(l) => l.scatter(ray_in, hit_record, sampler),
+            Material::Metal(m) => m.scatter(ray_in, hit_record, sampler),
+            Material::Dielectric(d) => d.scatter(ray_in, hit_record, sampler),
+            Material::DiffuseLight(l) => l.scatter(ray_in, hit_record, sampler),
+            Material::Isotropic(i) => i.scatter(ray_in, hit_record, sampler),
+        }
+    }
+
+    fn emitted(&self, hit_record: &HitRecord) -> Color {
         match self {
-            Material::Lambertian(l) => l.scatter(ray_in, hit_record),
-            Material::Metal(m) => m.scatter(ray_in, hit_record),
-            Material::Dielectric(d) => d.scatter(ray_in, hit_record),
-            _ => None,
+            Material::DiffuseLight(l) => l.emitted(hit_record),
+            _ => Color::black(),
         }
     }
 }
 
-#[derive(Clone, Copy)]
+impl Material {
+    /// base color of the surface at the hit point
+    pub fn albedo(&self, hit_record: &HitRecord) -> Color {
+        match self {
+            Material::Lambertian(l) => {
+                l.albedo
+                    .value(hit_record.u, hit_record.v, &hit_record.point)
+            }
+            Material::Metal(m) => m
+                .albedo
+                .value(hit_record.u, hit_record.v, &hit_record.point),
+            Material::Dielectric(_) => Color::white(),
+            Material::DiffuseLight(l) => l.emit,
+            Material::Isotropic(i) => i
+                .albedo
+                .value(hit_record.u, hit_record.v, &hit_record.point),
+        }
+    }
+
+    /// Phong exponent of the specular highlight, `None` for matte surfaces
+    pub fn specular(&self) -> Option<f64> {
+        match self {
+            Material::Lambertian(l) => l.specular,
+            // the sharper the reflection, the smaller the highlight
+            Material::Metal(m) => Some(2. / (m.fuzz * m.fuzz).max(0.002)),
+            Material::Dielectric(_) => Some(1000.),
+            Material::DiffuseLight(_) | Material::Isotropic(_) => None,
+        }
+    }
+
+    /// fraction of the light that is mirrored, per channel, for Whitted ray tracing
+    pub fn reflective(&self, hit_record: &HitRecord) -> Color {
+        match self {
+            Material::Lambertian(l) => Color::white() * l.reflective,
+            Material::Metal(_) => self.albedo(hit_record),
+            Material::Dielectric(_) | Material::DiffuseLight(_) | Material::Isotropic(_) => {
+                Color::black()
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: SharedTexture,
+    /// Phong exponent, only used by Whitted ray tracing
+    pub specular: Option<f64>,
+    /// fraction of mirror reflection, only used by Whitted ray tracing
+    pub reflective: f32,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
-        Self { albedo }
+        Self::textured(Arc::new(SolidColor(albedo)))
+    }
+
+    pub fn textured(albedo: SharedTexture) -> Self {
+        Self {
+            albedo,
+            specular: None,
+            reflective: 0.,
+        }
+    }
+
+    pub fn with_specular(mut self, specular: f64) -> Self {
+        self.specular = Some(specular);
+        self
+    }
+
+    pub fn with_reflective(mut self, reflective: f32) -> Self {
+        self.reflective = reflective;
+        self
     }
 }
 
 impl Scatterable for Lambertian {
-    fn scatter(&self, _ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
-        let mut scatter_direction = hit_record.normal + Point3D::random_in_unit_sphere();
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let mut scatter_direction = hit_record.normal + Point3D::random_in_unit_sphere(sampler);
 
         // catch degenerate scatter direction
         if scatter_direction.near_zero() {
             scatter_direction = hit_record.normal;
         }
 
-        let scattered = Ray::new(hit_record.point, scatter_direction);
-        let attenuation = self.albedo;
+        let scattered = Ray::new(hit_record.point, scatter_direction).with_time(ray_in.time);
+        let attenuation = self
+            .albedo
+            .value(hit_record.u, hit_record.v, &hit_record.point);
         Some((scattered, attenuation))
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Metal {
-    pub albedo: Color,
+    pub albedo: SharedTexture,
     pub fuzz: f64,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::textured(Arc::new(SolidColor(albedo)), fuzz)
+    }
+
+    pub fn textured(albedo: SharedTexture, fuzz: f64) -> Self {
         Self {
             albedo,
             fuzz: if fuzz < 1. { fuzz } else { 1. },
@@ -71,18 +172,26 @@ impl Metal {
     }
 }
 
-fn reflect(incoming: Point3D, normal: Point3D) -> Point3D {
+pub fn reflect(incoming: Point3D, normal: Point3D) -> Point3D {
     incoming - 2.0 * incoming.dot(&normal) * normal
 }
 
 impl Scatterable for Metal {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
         let reflected = reflect(ray_in.direction.normalize(), hit_record.normal);
         let scattered = Ray::new(
             hit_record.point,
-            reflected + self.fuzz * Point3D::random_in_unit_sphere(),
-        );
-        let attenuation = self.albedo;
+            reflected + self.fuzz * Point3D::random_in_unit_sphere(sampler),
+        )
+        .with_time(ray_in.time);
+        let attenuation = self
+            .albedo
+            .value(hit_record.u, hit_record.v, &hit_record.point);
 
         if scattered.direction.dot(&hit_record.normal) <= 0. {
             return None;
@@ -103,7 +212,7 @@ impl Dielectric {
     }
 }
 
-fn refract(uv: Point3D, normal: Point3D, eta_over_eta_prime: f64) -> Point3D {
+pub fn refract(uv: Point3D, normal: Point3D, eta_over_eta_prime: f64) -> Point3D {
     let cos_theta = (-uv).dot(&normal).min(1.0);
     let r_out_perpendicular = eta_over_eta_prime * (uv + cos_theta * normal);
     let r_out_parallel = -(1. - r_out_perpendicular.length_squared()).abs().sqrt() * normal;
@@ -112,14 +221,19 @@ fn refract(uv: Point3D, normal: Point3D, eta_over_eta_prime: f64) -> Point3D {
 }
 
 /// use Schlick's approximation for reflectance
-fn reflectance(cos: f64, ref_idx: f64) -> f64 {
+pub fn reflectance(cos: f64, ref_idx: f64) -> f64 {
     let mut r0 = (1. - ref_idx) / (1. + ref_idx);
     r0 *= r0;
     r0 + (1. - r0) * (1. - cos).powi(5)
 }
 
 impl Scatterable for Dielectric {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
         let attenuation = Color::white();
         let refraction_ratio = if hit_record.front_face {
             1. / self.refraction_index
@@ -131,9 +245,8 @@ impl Scatterable for Dielectric {
         let cos_theta = (-unit_direction).dot(&hit_record.normal).min(1.);
         let sin_theta = (1. - cos_theta * cos_theta).sqrt();
 
-        let mut rng = rand::thread_rng();
         let direction = if refraction_ratio * sin_theta > 1.
-            || reflectance(cos_theta, refraction_ratio) > rng.gen::<f64>()
+            || reflectance(cos_theta, refraction_ratio) > sampler.get_1d()
         {
             // cannot refract
             reflect(unit_direction, hit_record.normal)
@@ -141,6 +254,69 @@ impl Scatterable for Dielectric {
             refract(unit_direction, hit_record.normal, refraction_ratio)
         };
 
-        Some((Ray::new(hit_record.point, direction), attenuation))
+        Some((
+            Ray::new(hit_record.point, direction).with_time(ray_in.time),
+            attenuation,
+        ))
+    }
+}
+
+/// Emits light in all directions and does not scatter incoming light
+#[derive(Clone, Copy)]
+pub struct DiffuseLight {
+    pub emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self { emit }
+    }
+}
+
+impl Scatterable for DiffuseLight {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        _hit_record: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        None
+    }
+
+    fn emitted(&self, _hit_record: &HitRecord) -> Color {
+        self.emit
+    }
+}
+
+/// Phase function of a participating medium: scatters equally in all directions
+#[derive(Clone)]
+pub struct Isotropic {
+    pub albedo: SharedTexture,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::textured(Arc::new(SolidColor(albedo)))
+    }
+
+    pub fn textured(albedo: SharedTexture) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Scatterable for Isotropic {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let scattered =
+            Ray::new(hit_record.point, Point3D::random_unit_vector(sampler)).with_time(ray_in.time);
+        let attenuation = self
+            .albedo
+            .value(hit_record.u, hit_record.v, &hit_record.point);
+
+        Some((scattered, attenuation))
     }
 }
@@ -0,0 +1,199 @@
+use std::ops::Mul;
+
+use crate::point3d::Point3D;
+
+/// 4x4 matrix for affine transformations, acting on column vectors
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Matrix4(pub [[f64; 4]; 4]);
+
+impl Matrix4 {
+    pub const fn identity() -> Self {
+        Self([
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn translation(offset: Point3D) -> Self {
+        let mut m = Self::identity();
+        m.0[0][3] = offset[0];
+        m.0[1][3] = offset[1];
+        m.0[2][3] = offset[2];
+        m
+    }
+
+    pub fn scaling(factors: Point3D) -> Self {
+        let mut m = Self::identity();
+        m.0[0][0] = factors[0];
+        m.0[1][1] = factors[1];
+        m.0[2][2] = factors[2];
+        m
+    }
+
+    /// counter-clockwise rotation around the x-axis, in degrees
+    pub fn rotation_x(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut m = Self::identity();
+        m.0[1][1] = cos;
+        m.0[1][2] = -sin;
+        m.0[2][1] = sin;
+        m.0[2][2] = cos;
+        m
+    }
+
+    /// counter-clockwise rotation around the y-axis, in degrees
+    pub fn rotation_y(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut m = Self::identity();
+        m.0[0][0] = cos;
+        m.0[0][2] = sin;
+        m.0[2][0] = -sin;
+        m.0[2][2] = cos;
+        m
+    }
+
+    /// counter-clockwise rotation around the z-axis, in degrees
+    pub fn rotation_z(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut m = Self::identity();
+        m.0[0][0] = cos;
+        m.0[0][1] = -sin;
+        m.0[1][0] = sin;
+        m.0[1][1] = cos;
+        m
+    }
+
+    /// Scaling by `scale`, then rotation by `rotate` degrees around the x, y
+    /// and z axes, then translation by `translate`
+    pub fn placement(translate: Point3D, rotate: Point3D, scale: Point3D) -> Self {
+        Self::translation(translate)
+            * Self::rotation_z(rotate[2])
+            * Self::rotation_y(rotate[1])
+            * Self::rotation_x(rotate[0])
+            * Self::scaling(scale)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.0[j][i];
+            }
+        }
+        Self(m)
+    }
+
+    /// Inverse by Gauss-Jordan elimination, `None` if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.0;
+        let mut inverse = Self::identity().0;
+
+        for column in 0..4 {
+            // partial pivoting
+            let pivot =
+                (column..4).max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))?;
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = 1. / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= scale;
+                inverse[column][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == column {
+                    continue;
+                }
+
+                let factor = a[row][column];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[column][j];
+                    inverse[row][j] -= factor * inverse[column][j];
+                }
+            }
+        }
+
+        Some(Self(inverse))
+    }
+
+    /// transform a position, including the translation
+    pub fn transform_point(&self, p: Point3D) -> Point3D {
+        let m = &self.0;
+
+        Point3D::new(
+            m[0][0] * p[0] + m[0][1] * p[1] + m[0][2] * p[2] + m[0][3],
+            m[1][0] * p[0] + m[1][1] * p[1] + m[1][2] * p[2] + m[1][3],
+            m[2][0] * p[0] + m[2][1] * p[1] + m[2][2] * p[2] + m[2][3],
+        )
+    }
+
+    /// transform a direction, ignoring the translation
+    pub fn transform_vector(&self, v: Point3D) -> Point3D {
+        let m = &self.0;
+
+        Point3D::new(
+            m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+            m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+            m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+        )
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.0[i][k] * rhs.0[k][j]).sum();
+            }
+        }
+        Self(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse() {
+        let m = Matrix4::translation(Point3D::new(1., -2., 3.))
+            * Matrix4::rotation_y(30.)
+            * Matrix4::rotation_x(-70.)
+            * Matrix4::scaling(Point3D::new(2., 0.5, 3.));
+        let product = m * m.inverse().unwrap();
+
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1. } else { 0. };
+                assert!((product.0[i][j] - expected).abs() < 1e-12);
+            }
+        }
+
+        assert!(Matrix4::scaling(Point3D::new(1., 0., 1.))
+            .inverse()
+            .is_none());
+    }
+
+    #[test]
+    fn rotation() {
+        let p = Matrix4::rotation_z(90.).transform_point(Point3D::new(1., 0., 0.));
+
+        assert!(p[0].abs() < 1e-12);
+        assert!((p[1] - 1.).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::{Isotropic, Material},
+    point3d::{Length, Point3D},
+    ray::Ray,
+    sampler::Sampler,
+};
+
+/// Fog or smoke of constant density filling a convex `boundary`.
+///
+/// A ray travelling through the medium scatters at a random distance, with an
+/// exponentially distributed free flight: the denser the medium, the sooner.
+/// Rays that make it through pass on to whatever lies behind it. The random
+/// distance comes from the sampler of `hit_sampled`; `hit` has no sampler and
+/// takes the median distance instead.
+pub struct ConstantMedium<H> {
+    pub boundary: H,
+    /// -1 / density
+    negative_inverse_density: f64,
+    phase_function: Material,
+}
+
+impl<H: Hittable> ConstantMedium<H> {
+    pub fn new(boundary: H, density: f64, phase_function: Isotropic) -> Self {
+        Self {
+            boundary,
+            negative_inverse_density: -1. / density,
+            phase_function: Material::Isotropic(phase_function),
+        }
+    }
+
+    /// where the ray scatters in the medium, after a free flight given by
+    /// `u`, uniform in (0, 1]
+    fn scatter(&self, ray: &Ray, t_min: f64, t_max: f64, u: f64) -> Option<HitRecord<'_>> {
+        // where the ray's line enters and leaves the boundary, even behind the origin
+        let enter = self.boundary.hit(ray, f64::NEG_INFINITY, f64::INFINITY)?.t;
+        let exit = self.boundary.hit(ray, enter + 0.0001, f64::INFINITY)?.t;
+
+        let enter = enter.max(t_min).max(0.);
+        let exit = exit.min(t_max);
+        if enter >= exit {
+            return None;
+        }
+
+        let ray_length = ray.direction.length();
+        let distance_inside = (exit - enter) * ray_length;
+        let hit_distance = self.negative_inverse_density * u.ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = enter + hit_distance / ray_length;
+
+        // the normal and the face are meaningless inside a volume
+        Some(HitRecord {
+            point: ray.at(t),
+            normal: Point3D::new(1., 0., 0.),
+            t,
+            u: 0.,
+            v: 0.,
+            front_face: true,
+            material: &self.phase_function,
+        })
+    }
+}
+
+impl<H: Hittable> Hittable for ConstantMedium<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.scatter(ray, t_min, t_max, 0.5)
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.scatter(ray, t_min, t_max, 1. - sampler.get_1d())
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        material::Lambertian,
+        sampler::{IndependentSampler, Sampler},
+        sphere::Sphere,
+    };
+
+    #[test]
+    fn density() {
+        let sphere = || {
+            Sphere::new(
+                Point3D::new(0., 0., 0.),
+                1.,
+                Material::Lambertian(Lambertian::new(Color::white())),
+            )
+        };
+        let ray = Ray::new(Point3D::new(0., 0., 5.), Point3D::new(0., 0., -1.));
+
+        // a dense medium scatters right where the ray enters
+        let dense = ConstantMedium::new(sphere(), 1e9, Isotropic::new(Color::white()));
+        let hit = dense.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((hit.t - 4.).abs() < 1e-6);
+
+        // and right away when the ray starts inside it
+        let inside = Ray::new(Point3D::new(0., 0., 0.5), Point3D::new(0., 0., -1.));
+        let hit = dense.hit(&inside, 0.001, f64::INFINITY).unwrap();
+        assert!((hit.t - 0.001).abs() < 1e-6);
+
+        // a thin one lets nearly every ray through
+        let thin = ConstantMedium::new(sphere(), 1e-9, Isotropic::new(Color::white()));
+        assert!(thin.hit(&ray, 0.001, f64::INFINITY).is_none());
+
+        // through a diameter of 2 at density 1, 1 - e^-2 of the rays scatter
+        let medium = ConstantMedium::new(sphere(), 1., Isotropic::new(Color::white()));
+        let mut sampler = IndependentSampler::new(3);
+        let scattered = (0..10_000)
+            .filter(|&i| {
+                sampler.start_pixel_sample(0, 0, i);
+                medium
+                    .hit_sampled(&ray, 0.001, f64::INFINITY, &mut sampler)
+                    .is_some()
+            })
+            .count();
+        assert!((scattered as f64 / 10_000. - (1. - (-2f64).exp())).abs() < 0.02);
+    }
+}
@@ -0,0 +1,258 @@
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    bvh::Bvh,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    point3d::{CrossProduct, DotProduct, Normalize, Point3D},
+    ray::Ray,
+    triangle,
+};
+
+struct MeshData {
+    positions: Vec<Point3D>,
+    /// per-vertex normals, for smooth shading
+    normals: Option<Vec<Point3D>>,
+    /// per-vertex texture coordinates
+    texcoords: Option<Vec<[f64; 2]>>,
+    indices: Vec<[usize; 3]>,
+    material: Material,
+}
+
+/// Single triangle of a mesh, referring to the shared vertex data by index
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    index: usize,
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [Point3D; 3] {
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+        let positions = &self.mesh.positions;
+
+        [positions[i0], positions[i1], positions[i2]]
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let [v0, v1, v2] = self.vertices();
+        let (t, b1, b2) = triangle::intersect(v0, v1, v2, ray, t_min, t_max)?;
+        let b0 = 1. - b1 - b2;
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+
+        let geometric_normal = (v1 - v0).cross(&(v2 - v0)).normalize();
+        let front_face = ray.direction.dot(&geometric_normal) < 0.0;
+
+        // interpolate the vertex normals, keeping them on the side of the geometric normal
+        let normal = match &self.mesh.normals {
+            Some(normals) => {
+                let n = (b0 * normals[i0] + b1 * normals[i1] + b2 * normals[i2]).normalize();
+                if n.dot(&geometric_normal) < 0. {
+                    -n
+                } else {
+                    n
+                }
+            }
+            None => geometric_normal,
+        };
+
+        let (u, v) = match &self.mesh.texcoords {
+            Some(texcoords) => (
+                b0 * texcoords[i0][0] + b1 * texcoords[i1][0] + b2 * texcoords[i2][0],
+                b0 * texcoords[i0][1] + b1 * texcoords[i1][1] + b2 * texcoords[i2][1],
+            ),
+            None => (b1, b2),
+        };
+
+        Some(HitRecord {
+            point: ray.at(t),
+            normal: if front_face { normal } else { -normal },
+            t,
+            u,
+            v,
+            front_face,
+            material: &self.mesh.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let [v0, v1, v2] = self.vertices();
+
+        Some(triangle::bounding_box(v0, v1, v2))
+    }
+}
+
+/// Indexed triangle mesh with optional per-vertex normals and texture coordinates
+pub struct TriangleMesh {
+    triangles: Bvh<MeshTriangle>,
+}
+
+impl TriangleMesh {
+    /// Triangles whose vertices lie on a line are left out, as they have no
+    /// area and no normal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of range of the positions.
+    pub fn new(
+        positions: Vec<Point3D>,
+        indices: Vec<[usize; 3]>,
+        normals: Option<Vec<Point3D>>,
+        texcoords: Option<Vec<[f64; 2]>>,
+        material: Material,
+    ) -> Self {
+        assert!(
+            indices.iter().flatten().all(|&i| i < positions.len()),
+            "mesh vertex index out of range"
+        );
+
+        let mesh = Arc::new(MeshData {
+            positions,
+            normals,
+            texcoords,
+            indices,
+            material,
+        });
+
+        let triangles = (0..mesh.indices.len())
+            .map(|index| MeshTriangle {
+                mesh: Arc::clone(&mesh),
+                index,
+            })
+            .filter(|triangle| {
+                let [v0, v1, v2] = triangle.vertices();
+                !triangle::is_degenerate(v0, v1, v2)
+            })
+            .collect();
+
+        Self {
+            triangles: Bvh::new(triangles),
+        }
+    }
+
+    /// Load all models of a Wavefront OBJ file into a single mesh.
+    ///
+    /// Faces are triangulated; normals and texture coordinates are only used
+    /// if every vertex has one.
+    pub fn from_obj<P: AsRef<Path>>(path: P, material: Material) -> Result<Self, tobj::LoadError> {
+        let options = tobj::LoadOptions {
+            single_index: true,
+            triangulate: true,
+            ignore_points: true,
+            ignore_lines: true,
+        };
+        let (models, _materials) = tobj::load_obj(path.as_ref(), &options)?;
+
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut texcoords = Vec::new();
+        let mut indices = Vec::new();
+
+        for model in models {
+            let mesh = model.mesh;
+            let offset = positions.len();
+
+            positions.extend(
+                mesh.positions
+                    .chunks_exact(3)
+                    .map(|p| Point3D::new(p[0].into(), p[1].into(), p[2].into())),
+            );
+            normals.extend(
+                mesh.normals
+                    .chunks_exact(3)
+                    .map(|n| Point3D::new(n[0].into(), n[1].into(), n[2].into())),
+            );
+            texcoords.extend(
+                mesh.texcoords
+                    .chunks_exact(2)
+                    .map(|t| [t[0].into(), t[1].into()]),
+            );
+            indices.extend(mesh.indices.chunks_exact(3).map(|i| {
+                [
+                    offset + i[0] as usize,
+                    offset + i[1] as usize,
+                    offset + i[2] as usize,
+                ]
+            }));
+        }
+
+        if indices.iter().flatten().any(|&i| i >= positions.len()) {
+            return Err(tobj::LoadError::FaceVertexOutOfBounds);
+        }
+
+        let normals = (normals.len() == positions.len()).then_some(normals);
+        let texcoords = (texcoords.len() == positions.len()).then_some(texcoords);
+
+        Ok(Self::new(positions, indices, normals, texcoords, material))
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.triangles.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.triangles.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Lambertian};
+
+    #[test]
+    fn smooth_normals() {
+        let mesh = TriangleMesh::from_obj(
+            "scenes/octahedron.obj",
+            Material::Lambertian(Lambertian::new(Color::white())),
+        )
+        .unwrap();
+        assert_eq!(mesh.len(), 8);
+
+        // the octahedron's vertex normals point away from the center, so the
+        // interpolated normal matches that of the circumscribed sphere
+        let ray = Ray::new(Point3D::new(5., 0.2, 0.1), Point3D::new(-1., 0., 0.));
+        let hit = mesh.hit(&ray, 0.001, f64::INFINITY).unwrap();
+
+        assert!(hit.front_face);
+        assert!((hit.normal.dot(&hit.point.normalize()) - 1.).abs() < 1e-9);
+        assert!(hit.normal[1] > 0. && hit.normal[2] > 0.);
+    }
+
+    #[test]
+    fn degenerate() {
+        let material = Material::Lambertian(Lambertian::new(Color::white()));
+
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("raytracer-degenerate-{}.obj", std::process::id()));
+        std::fs::write(
+            &path,
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 2 0 0\nf 1 2 3\nf 1 2 4\n",
+        )
+        .unwrap();
+        let mesh = TriangleMesh::from_obj(&path, material.clone());
+        std::fs::remove_file(&path).unwrap();
+
+        // the face along the x axis has no area
+        assert_eq!(mesh.unwrap().len(), 1);
+
+        let path = dir.join(format!("raytracer-out-of-range-{}.obj", std::process::id()));
+        std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 9\n").unwrap();
+        let mesh = TriangleMesh::from_obj(&path, material);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(mesh.is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    point3d::Point3D,
+    ray::Ray,
+    sampler::Sampler,
+};
+
+/// Moves an object in a straight line by `offset` between `time0` and `time1`,
+/// staying put before and after.
+///
+/// The object is intersected at its starting position with a ray shifted back
+/// by the displacement at the ray's time.
+pub struct Moving<H> {
+    pub object: H,
+    pub offset: Point3D,
+    pub time0: f64,
+    pub time1: f64,
+}
+
+impl<H: Hittable> Moving<H> {
+    pub fn new(object: H, offset: Point3D, time0: f64, time1: f64) -> Self {
+        Self {
+            object,
+            offset,
+            time0,
+            time1,
+        }
+    }
+
+    /// displacement from the starting position at `time`
+    pub fn displacement(&self, time: f64) -> Point3D {
+        if self.time1 <= self.time0 {
+            return Point3D::new(0., 0., 0.);
+        }
+
+        let s = ((time - self.time0) / (self.time1 - self.time0)).clamp(0., 1.);
+        s * self.offset
+    }
+
+    /// `hit` the object with the ray shifted back to its starting position
+    fn hit_moved<'a>(
+        &'a self,
+        ray: &Ray,
+        hit: impl FnOnce(&'a H, &Ray) -> Option<HitRecord<'a>>,
+    ) -> Option<HitRecord<'a>> {
+        let displacement = self.displacement(ray.time);
+        let moved_ray = Ray::new(ray.origin - displacement, ray.direction).with_time(ray.time);
+
+        let mut hit = hit(&self.object, &moved_ray)?;
+        hit.point = hit.point + displacement;
+
+        Some(hit)
+    }
+}
+
+impl<H: Hittable> Hittable for Moving<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.hit_moved(ray, |object, ray| object.hit(ray, t_min, t_max))
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.hit_moved(ray, |object, ray| {
+            object.hit_sampled(ray, t_min, t_max, sampler)
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bbox = self.object.bounding_box()?;
+        let end = Aabb::new(bbox.min + self.offset, bbox.max + self.offset);
+
+        Some(bbox.surrounding(&end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        cuboid::Cuboid,
+        material::{Lambertian, Material},
+    };
+
+    #[test]
+    fn moving_box() {
+        let cuboid = Cuboid::new(
+            Point3D::new(-1., -1., -1.),
+            Point3D::new(1., 1., 1.),
+            Material::Lambertian(Lambertian::new(Color::white())),
+        );
+        let moving = Moving::new(cuboid, Point3D::new(4., 0., 0.), 0., 1.);
+
+        let ray =
+            |time| Ray::new(Point3D::new(0., 0., 5.), Point3D::new(0., 0., -1.)).with_time(time);
+        assert_eq!(moving.hit(&ray(0.), 0.001, f64::INFINITY).unwrap().t, 4.);
+        assert!(moving.hit(&ray(1.), 0.001, f64::INFINITY).is_none());
+
+        let ray = Ray::new(Point3D::new(4., 0., 5.), Point3D::new(0., 0., -1.)).with_time(2.);
+        let hit = moving.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert_eq!(hit.point[0], 4.);
+
+        let bbox = moving.bounding_box().unwrap();
+        assert_eq!((bbox.min[0], bbox.max[0]), (-1., 5.));
+    }
+}
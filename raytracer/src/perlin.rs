@@ -0,0 +1,113 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
+
+use crate::point3d::{DotProduct, Normalize, Point3D};
+
+const POINT_COUNT: usize = 256;
+
+/// Perlin gradient noise over a lattice of random unit vectors
+pub struct Perlin {
+    gradients: Vec<Point3D>,
+    permutations: [Vec<usize>; 3],
+}
+
+impl Perlin {
+    /// lattice drawn from `rng`
+    pub fn new(rng: &mut dyn RngCore) -> Self {
+        let gradients = (0..POINT_COUNT)
+            .map(|_| {
+                Point3D::new(
+                    rng.gen_range(-1.0..1.),
+                    rng.gen_range(-1.0..1.),
+                    rng.gen_range(-1.0..1.),
+                )
+                .normalize()
+            })
+            .collect();
+
+        let mut permutation = || {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(rng);
+            p
+        };
+
+        Self {
+            permutations: [permutation(), permutation(), permutation()],
+            gradients,
+        }
+    }
+
+    /// smooth noise in [-1, 1]
+    pub fn noise(&self, p: &Point3D) -> f64 {
+        let floor = [p[0].floor(), p[1].floor(), p[2].floor()];
+        let fraction = [p[0] - floor[0], p[1] - floor[1], p[2] - floor[2]];
+        let [i, j, k] = floor.map(|f| f as i64);
+
+        let [perm_x, perm_y, perm_z] = &self.permutations;
+        let mut accumulated = 0.;
+
+        // Hermite-smoothed trilinear interpolation of the corner gradients
+        let [u, v, w] = fraction.map(|f| f * f * (3. - 2. * f));
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let gradient = self.gradients[perm_x[((i + di) & 255) as usize]
+                        ^ perm_y[((j + dj) & 255) as usize]
+                        ^ perm_z[((k + dk) & 255) as usize]];
+                    let weight = Point3D::new(
+                        fraction[0] - di as f64,
+                        fraction[1] - dj as f64,
+                        fraction[2] - dk as f64,
+                    );
+
+                    accumulated += (di as f64 * u + (1 - di) as f64 * (1. - u))
+                        * (dj as f64 * v + (1 - dj) as f64 * (1. - v))
+                        * (dk as f64 * w + (1 - dk) as f64 * (1. - w))
+                        * gradient.dot(&weight);
+                }
+            }
+        }
+
+        accumulated
+    }
+
+    /// sum of `depth` octaves of noise, each at twice the frequency and half the weight
+    pub fn turbulence(&self, p: &Point3D, depth: usize) -> f64 {
+        let mut accumulated = 0.;
+        let mut p = *p;
+        let mut weight = 1.;
+
+        for _ in 0..depth {
+            accumulated += weight * self.noise(&p);
+            weight *= 0.5;
+            p = 2. * p;
+        }
+
+        accumulated.abs()
+    }
+}
+
+/// the same lattice every time, so that renders are reproducible
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::new(&mut StdRng::seed_from_u64(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_range() {
+        let perlin = Perlin::default();
+
+        for i in 0..1000 {
+            let x = i as f64 * 0.137;
+            let p = Point3D::new(x, 0.5 * x, -0.3 * x);
+            assert!(perlin.noise(&p).abs() <= 1.);
+        }
+
+        // gradient noise vanishes on the lattice
+        assert_eq!(perlin.noise(&Point3D::new(3., -2., 7.)), 0.);
+    }
+}
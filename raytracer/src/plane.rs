@@ -0,0 +1,104 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    point3d::{CrossProduct, DotProduct, Normalize, Point3D},
+    ray::Ray,
+};
+
+/// Infinite plane through `point`, facing along `normal`
+#[derive(Clone)]
+pub struct Plane {
+    pub point: Point3D,
+    pub normal: Point3D,
+    pub material: Material,
+}
+
+impl Plane {
+    pub fn new(point: Point3D, normal: Point3D, material: Material) -> Self {
+        Self {
+            point,
+            normal: normal.normalize(),
+            material,
+        }
+    }
+
+    /// orthonormal vectors spanning the plane, used for the surface coordinates
+    fn tangents(&self) -> (Point3D, Point3D) {
+        let helper = if self.normal[0].abs() > 0.9 {
+            Point3D::new(0., 1., 0.)
+        } else {
+            Point3D::new(1., 0., 0.)
+        };
+        let tangent = helper.cross(&self.normal).normalize();
+
+        (tangent, self.normal.cross(&tangent))
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(&ray.direction);
+
+        // ray is parallel to the plane
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+
+        let t = (self.point - ray.origin).dot(&self.normal) / denominator;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let (tangent, bitangent) = self.tangents();
+        let front_face = denominator < 0.0;
+
+        Some(HitRecord {
+            point: p,
+            normal: if front_face {
+                self.normal
+            } else {
+                -self.normal
+            },
+            t,
+            u: (p - self.point).dot(&tangent),
+            v: (p - self.point).dot(&bitangent),
+            front_face,
+            material: &self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Lambertian};
+
+    #[test]
+    fn hit() {
+        let plane = Plane::new(
+            Point3D::new(0., -1., 0.),
+            Point3D::new(0., 2., 0.),
+            Material::Lambertian(Lambertian::new(Color::white())),
+        );
+
+        let above = Ray::new(Point3D::new(3., 1., 7.), Point3D::new(0., -1., 0.));
+        let hit = plane.hit(&above, 0.001, f64::INFINITY).unwrap();
+        assert_eq!(hit.t, 2.);
+        assert!(hit.front_face);
+        assert_eq!(hit.normal[1], 1.);
+
+        let below = Ray::new(Point3D::new(3., -3., 7.), Point3D::new(0., 1., 0.));
+        let hit = plane.hit(&below, 0.001, f64::INFINITY).unwrap();
+        assert!(!hit.front_face);
+        assert_eq!(hit.normal[1], -1.);
+
+        let parallel = Ray::new(Point3D::new(3., 1., 7.), Point3D::new(1., 0., 0.));
+        assert!(plane.hit(&parallel, 0.001, f64::INFINITY).is_none());
+    }
+}
@@ -1,6 +1,6 @@
 use std::ops::{Add, Div, Index, Mul, Neg, Sub};
 
-use rand::Rng;
+use crate::sampler::Sampler;
 
 pub trait DotProduct {
     type Output;
@@ -45,34 +45,29 @@ impl Point3D {
         Self { p: [x, y, z] }
     }
 
-    fn random(min: f64, max: f64) -> Self {
-        let mut rng = rand::thread_rng();
-
-        Point3D::new(
-            rng.gen_range(min..max),
-            rng.gen_range(min..max),
-            rng.gen_range(min..max),
-        )
+    /// uniformly distributed in the unit ball
+    pub fn random_in_unit_sphere(sampler: &mut dyn Sampler) -> Self {
+        let direction = Point3D::random_unit_vector(sampler);
+        sampler.get_1d().cbrt() * direction
     }
 
-    pub fn random_in_unit_sphere() -> Self {
-        loop {
-            let p = Point3D::random(-1., 1.);
-            if p.length_squared() < 1. {
-                return p;
-            }
-        }
+    /// uniformly distributed on the unit sphere
+    pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Self {
+        let (u, v) = sampler.get_2d();
+        let z = 1. - 2. * u;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * std::f64::consts::PI * v;
+
+        Point3D::new(r * phi.cos(), r * phi.sin(), z)
     }
 
-    pub fn random_in_unit_disk() -> Self {
-        let mut rng = rand::thread_rng();
+    /// uniformly distributed in the unit disk in the xy plane
+    pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Self {
+        let (u, v) = sampler.get_2d();
+        let r = u.sqrt();
+        let theta = 2. * std::f64::consts::PI * v;
 
-        loop {
-            let p = Point3D::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.);
-            if p.length_squared() < 1. {
-                return p;
-            }
-        }
+        Point3D::new(r * theta.cos(), r * theta.sin(), 0.)
     }
 
     /// return true if the vector is near 0 in all dimensions
@@ -3,11 +3,22 @@ use crate::point3d::Point3D;
 pub struct Ray {
     pub origin: Point3D,
     pub direction: Point3D,
+    /// moment within the camera's shutter interval at which the ray was cast
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Point3D, direction: Point3D) -> Self {
-        Self { origin, direction }
+        Self {
+            origin,
+            direction,
+            time: 0.,
+        }
+    }
+
+    pub fn with_time(mut self, time: f64) -> Self {
+        self.time = time;
+        self
     }
 
     pub fn at(&self, t: f64) -> Point3D {
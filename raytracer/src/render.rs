@@ -1,106 +1,470 @@
 use crate::{
+    accumulator::{fingerprint, Accumulator},
+    aov::Features,
     color::Color,
-    hittable::{HitRecord, Hittable},
-    material::Scatterable,
-    point3d::Normalize,
+    framebuffer::Framebuffer,
     ray::Ray,
+    sampler::Sampler,
     scene::Scene,
-    sphere::Sphere,
 };
 
-use std::fs::File;
-
-use image::{codecs::png::PngEncoder, ColorType, ImageEncoder};
-
-use rand::Rng;
+use std::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
 
 use rayon::prelude::*;
 
-/// Conceptually, an "infinitesimaly small" real number
-const EPSILON: f64 = 0.001;
-
+/// Render the scene and write it to `filename` with the scene's display
+/// transform, see `Framebuffer::write`. Denoised if the scene has a denoiser.
 pub fn render(scene: &Scene, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let mut pixels = vec![0; scene.width * scene.height * 3];
-    let lines: Vec<(usize, &mut [u8])> = pixels.chunks_mut(scene.width * 3).enumerate().collect();
+    let mut framebuffer = render_framebuffer(scene);
+    if let Some(denoiser) = &scene.denoiser {
+        framebuffer = denoiser.apply(&framebuffer, &Features::render(scene));
+    }
+    framebuffer.write(filename, &scene.display)?;
 
-    // render
-    lines.into_par_iter().for_each(|(y, line)| {
-        render_line(line, scene, y);
-    });
+    Ok(())
+}
 
-    write_image(filename, &pixels, scene.width, scene.height)
-        .expect("Failed to write image to file");
+/// Render the scene to linear radiance, averaged over the samples of every pixel
+pub fn render_framebuffer(scene: &Scene) -> Framebuffer {
+    let mut accumulator = Accumulator::new(scene.width, scene.height, 0);
+    match scene.adaptive {
+        Some(_) => {
+            let samples_per_pass = scene.samples_per_pixel.min(SAMPLES_PER_ADAPTIVE_PASS);
+            render_progressive(scene, &mut accumulator, samples_per_pass, &|_| {}, |_| {
+                Ok::<(), ()>(())
+            })
+            .expect("nothing to fail after a pass");
+        }
+        None => render_pass(scene, &mut accumulator, scene.samples_per_pixel),
+    }
 
-    Ok(())
+    accumulator.framebuffer()
 }
 
-fn render_line(pixels: &mut [u8], scene: &Scene, y: usize) {
-    let mut rng = rand::thread_rng();
+/// side of the square tiles the image is split into for rendering in parallel
+const TILE_SIZE: usize = 16;
 
-    for x in 0..scene.width {
-        let mut color = Color::black();
+/// without passes, adaptive sampling still needs to look at the variance every
+/// so many samples
+const SAMPLES_PER_ADAPTIVE_PASS: usize = 16;
 
-        for _s in 0..scene.samples_per_pixel {
-            let u = (x as f64 + rng.gen::<f64>()) / (scene.width as f64 - 1.0);
-            let v =
-                (scene.height as f64 - y as f64 + rng.gen::<f64>()) / (scene.height as f64 - 1.0);
+/// Adaptive rendering stops once a pass would take less than this fraction
+/// of the samples of a full pass: the few pixels left are unlikely to ever
+/// converge, and would otherwise spread the rest of the budget over
+/// countless passes.
+const MIN_ADAPTIVE_PASS: f64 = 1. / 32.;
 
-            let ray = scene.camera.get_ray(u, v);
+/// How far a render has come, reported after every finished tile
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    pub tiles_done: usize,
+    /// camera rays traced so far
+    pub samples_done: u64,
+    /// camera rays the render will trace at most
+    pub samples_total: u64,
+    /// camera rays traced per second so far
+    pub samples_per_second: f64,
+    pub elapsed: Duration,
+    /// estimated time until the render is done
+    pub eta: Duration,
+}
 
-            color += ray_color(&ray, &scene.objects, scene.depth_max);
+impl Progress {
+    /// fraction of the work that is done, in [0, 1]
+    pub fn fraction(&self) -> f64 {
+        (self.samples_done as f64 / self.samples_total.max(1) as f64).clamp(0., 1.)
+    }
+}
+
+/// Shared between the threads rendering the tiles of one or more passes
+struct ProgressTracker<'a> {
+    start: Instant,
+    tiles_done: AtomicUsize,
+    samples_total: u64,
+    samples_done: AtomicU64,
+    report: &'a (dyn Fn(&Progress) + Sync),
+}
+
+impl<'a> ProgressTracker<'a> {
+    fn new(samples_total: u64, report: &'a (dyn Fn(&Progress) + Sync)) -> Self {
+        Self {
+            start: Instant::now(),
+            tiles_done: AtomicUsize::new(0),
+            samples_total,
+            samples_done: AtomicU64::new(0),
+            report,
         }
+    }
 
-        let bytes = color.to_bytes(scene.samples_per_pixel);
+    fn tile_done(&self, samples: u64) {
+        let tiles_done = self.tiles_done.fetch_add(1, Ordering::Relaxed) + 1;
+        let samples_done = self.samples_done.fetch_add(samples, Ordering::Relaxed) + samples;
 
-        pixels[x * 3] = bytes[0];
-        pixels[x * 3 + 1] = bytes[1];
-        pixels[x * 3 + 2] = bytes[2];
+        let elapsed = self.start.elapsed();
+        let samples_per_second = samples_done as f64 / elapsed.as_secs_f64().max(1e-9);
+        let samples_left = self.samples_total.saturating_sub(samples_done);
+
+        (self.report)(&Progress {
+            tiles_done,
+            samples_done,
+            samples_total: self.samples_total,
+            samples_per_second,
+            elapsed,
+            eta: Duration::from_secs_f64(samples_left as f64 / samples_per_second.max(1e-9)),
+        });
     }
 }
 
-fn ray_color(ray: &Ray, scene: &[Sphere], depth: isize) -> Color {
-    if depth <= 0 {
-        return Color::black();
-    }
+/// Fingerprint of what the samples of a checkpoint depend on: the scene
+/// `source`, the scene's sampler and depth, and the `settings` that override
+/// the scene. The `samples_per_pixel` of the source and the scene are left
+/// out, so that a later render can add samples to a checkpoint.
+pub fn checkpoint_fingerprint(source: &str, scene: &Scene, settings: &str) -> u64 {
+    // built-in scenes are given by their name, which is no TOML
+    let source = match source.parse::<toml::Table>() {
+        Ok(mut table) => {
+            if let Some(image) = table.get_mut("image").and_then(|i| i.as_table_mut()) {
+                image.remove("samples_per_pixel");
+            }
+            table.to_string()
+        }
+        Err(_) => source.to_string(),
+    };
+
+    fingerprint(&format!(
+        "{source}\n{:?} {} {settings}",
+        scene.sampler, scene.depth_max
+    ))
+}
 
-    if let Some(hit_record) = hit_world(&scene, ray, EPSILON, f64::INFINITY) {
-        if let Some((scattered, attenuation)) = hit_record.material.scatter(ray, &hit_record) {
-            return attenuation * ray_color(&scattered, scene, depth - 1);
+/// Render in passes of `samples_per_pass` until every pixel has
+/// `scene.samples_per_pixel` samples, calling `progress` after every tile and
+/// `after_pass` after each pass, e.g. to write an intermediate image and a
+/// checkpoint. Passes take at least one sample per pixel.
+///
+/// A resumed accumulator only gets the samples it is still missing. With
+/// `scene.adaptive`, `scene.samples_per_pixel` is an average: converged pixels
+/// are skipped and the budget goes to the noisy ones, until it is spent, every
+/// pixel has converged or only a handful of pixels are left. Stops at the
+/// first error returned by `after_pass`.
+pub fn render_progressive<E>(
+    scene: &Scene,
+    accumulator: &mut Accumulator,
+    samples_per_pass: usize,
+    progress: &(dyn Fn(&Progress) + Sync),
+    mut after_pass: impl FnMut(&Accumulator) -> Result<(), E>,
+) -> Result<(), E> {
+    let samples_per_pass = samples_per_pass.max(1);
+    let pixels = scene.width * scene.height;
+
+    let budget = (scene.samples_per_pixel * pixels) as u64;
+    let missing = match scene.adaptive {
+        Some(_) => budget.saturating_sub(accumulator.total_samples()),
+        None => {
+            let missing = scene
+                .samples_per_pixel
+                .saturating_sub(accumulator.samples_per_pixel() as usize);
+            (missing * pixels) as u64
+        }
+    };
+    let tracker = ProgressTracker::new(missing, progress);
+
+    loop {
+        let plan = match &scene.adaptive {
+            Some(adaptive) if accumulator.total_samples() < budget => {
+                accumulator.adaptive_plan(adaptive, samples_per_pass)
+            }
+            Some(_) => return Ok(()),
+            None => {
+                let missing = scene
+                    .samples_per_pixel
+                    .saturating_sub(accumulator.samples_per_pixel() as usize);
+                vec![missing.min(samples_per_pass) as u32; pixels]
+            }
+        };
+        let planned: u64 = plan.iter().map(|&samples| samples as u64).sum();
+        let full_pass = (samples_per_pass * pixels) as f64;
+        if planned == 0
+            || scene.adaptive.is_some() && (planned as f64) < MIN_ADAPTIVE_PASS * full_pass
+        {
+            return Ok(());
         }
 
-        return Color::black();
+        render_tiles(scene, accumulator, &plan, Some(&tracker));
+        after_pass(accumulator)?;
     }
+}
 
-    let unit_direction = ray.direction.normalize();
-    let t = 0.5 * (unit_direction[1] + 1.0);
-    (1.0 - t as f32) * Color(1., 1., 1.) + t as f32 * Color(0.5, 0.7, 1.0)
+/// Add `samples` samples to every pixel of the accumulator
+pub fn render_pass(scene: &Scene, accumulator: &mut Accumulator, samples: usize) {
+    let plan = vec![samples as u32; accumulator.width * accumulator.height];
+    render_tiles(scene, accumulator, &plan, None);
 }
 
-fn hit_world(world: &[Sphere], ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-    let mut hit_record = None;
-    let mut t_closest = t_max;
+/// pixel ranges `(x0, y0, x1, y1)` of the tiles covering the image, row by row
+fn tiles(width: usize, height: usize) -> Vec<(usize, usize, usize, usize)> {
+    (0..height)
+        .step_by(TILE_SIZE)
+        .flat_map(|y0| {
+            (0..width).step_by(TILE_SIZE).map(move |x0| {
+                (
+                    x0,
+                    y0,
+                    (x0 + TILE_SIZE).min(width),
+                    (y0 + TILE_SIZE).min(height),
+                )
+            })
+        })
+        .collect()
+}
+
+/// add `plan[i]` samples to pixel `i` of the accumulator
+fn render_tiles(
+    scene: &Scene,
+    accumulator: &mut Accumulator,
+    plan: &[u32],
+    tracker: Option<&ProgressTracker>,
+) {
+    let seed = accumulator.seed;
+    let counts = accumulator.sample_counts();
+    let tiles = tiles(accumulator.width, accumulator.height);
+
+    // render
+    let rendered: Vec<Vec<(Color, f64)>> = tiles
+        .par_iter()
+        .map(|&tile| {
+            let sums = render_tile(tile, scene, plan, counts, seed);
 
-    for i in world.iter() {
-        if let Some(hit) = i.hit(ray, t_min, t_closest) {
-            t_closest = hit.t;
-            hit_record = Some(hit);
+            if let Some(tracker) = tracker {
+                let (x0, y0, x1, y1) = tile;
+                let samples = (y0..y1)
+                    .flat_map(|y| &plan[y * scene.width + x0..y * scene.width + x1])
+                    .map(|&samples| samples as u64)
+                    .sum();
+                tracker.tile_done(samples);
+            }
+            sums
+        })
+        .collect();
+
+    for ((x0, y0, x1, _), sums) in tiles.into_iter().zip(rendered) {
+        let width = x1 - x0;
+        for (i, (sum, squares)) in sums.into_iter().enumerate() {
+            let (x, y) = (x0 + i % width, y0 + i / width);
+            accumulator.add(x, y, sum, squares, plan[y * accumulator.width + x]);
         }
     }
 
-    hit_record
+    accumulator.passes += 1;
 }
 
-/// produce image of scene
-fn write_image(
-    filename: &str,
-    pixels: &[u8],
-    width: usize,
-    height: usize,
-) -> Result<(), std::io::Error> {
-    let output = File::create(filename)?;
-    let encoder = PngEncoder::new(output);
-    encoder
-        .write_image(pixels, width as u32, height as u32, ColorType::Rgb8)
+/// sums of the samples and of their squared luminance for every pixel of the
+/// tile, row by row, taking as many samples as the `plan` says after the
+/// `counts` each pixel already has
+fn render_tile(
+    (x0, y0, x1, y1): (usize, usize, usize, usize),
+    scene: &Scene,
+    plan: &[u32],
+    counts: &[u32],
+    seed: u64,
+) -> Vec<(Color, f64)> {
+    let mut sums = Vec::with_capacity((x1 - x0) * (y1 - y0));
+    let mut sampler = scene.sampler.build(seed, scene.samples_per_pixel);
+
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let mut sum = Color::black();
+            let mut squares = 0.;
+
+            let i = y * scene.width + x;
+            for index in counts[i]..counts[i] + plan[i] {
+                sampler.start_pixel_sample(x, y, index);
+                let ray = camera_ray(scene, x, y, sampler.as_mut());
+                let radiance = scene.integrator.radiance(&ray, scene, sampler.as_mut());
+                sum += radiance;
+                squares += (radiance.luminance() as f64).powi(2);
+            }
+
+            sums.push((sum, squares));
+        }
+    }
+
+    sums
+}
+
+/// camera ray through a random point of pixel (x, y)
+pub(crate) fn camera_ray(scene: &Scene, x: usize, y: usize, sampler: &mut dyn Sampler) -> Ray {
+    let (dx, dy) = sampler.get_2d();
+    let u = (x as f64 + dx) / (scene.width as f64 - 1.0);
+    let v = (scene.height as f64 - y as f64 + dy) / (scene.height as f64 - 1.0);
+
+    scene.camera.get_ray(u, v, sampler)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = r#"
+[image]
+width = 4
+height = 3
+samples_per_pixel = 5
+max_depth = 2
+
+[camera]
+look_from = [0.0, 0.0, 1.0]
+look_at = [0.0, 0.0, 0.0]
+vfov = 90.0
+"#;
+
+    #[test]
+    fn progressive() {
+        let mut scene = Scene::parse(SCENE).unwrap();
+        let mut accumulator = Accumulator::new(scene.width, scene.height, 7);
+
+        let mut passes = Vec::new();
+        render_progressive(&scene, &mut accumulator, 2, &|_| {}, |accumulator| {
+            passes.push(accumulator.samples_per_pixel());
+            Ok::<(), ()>(())
+        })
         .unwrap();
-    Ok(())
+        assert_eq!(passes, [2, 4, 5]);
+
+        // resuming with a higher target only adds the missing samples
+        scene.samples_per_pixel = 8;
+        let tiles_done = AtomicUsize::new(0);
+        let progress = |progress: &Progress| {
+            tiles_done.fetch_max(progress.tiles_done, Ordering::Relaxed);
+            assert_eq!(progress.samples_total, 3 * 12);
+        };
+        render_progressive(
+            &scene,
+            &mut accumulator,
+            16,
+            &progress,
+            |_| Ok::<(), ()>(()),
+        )
+        .unwrap();
+        assert_eq!(tiles_done.into_inner(), 1);
+        assert_eq!(accumulator.samples_per_pixel(), 8);
+        assert_eq!(accumulator.passes, 4);
+
+        // the sky is seen everywhere, so every average is a sky color
+        let sky = accumulator.framebuffer().get(1, 1);
+        assert!(sky.2 > 0.9 && sky.0 < sky.2);
+    }
+
+    #[test]
+    fn resume_with_more_samples() {
+        let scene = Scene::parse(SCENE).unwrap();
+        let fingerprint = checkpoint_fingerprint(SCENE, &scene, "");
+        let mut accumulator =
+            Accumulator::new(scene.width, scene.height, 7).with_fingerprint(fingerprint);
+        render_progressive(&scene, &mut accumulator, 16, &|_| {}, |_| Ok::<(), ()>(())).unwrap();
+        assert_eq!(accumulator.samples_per_pixel(), 5);
+
+        // asking for more samples in the scene file keeps the checkpoint
+        let source = SCENE.replace("samples_per_pixel = 5", "samples_per_pixel = 12");
+        let scene = Scene::parse(&source).unwrap();
+        assert_eq!(checkpoint_fingerprint(&source, &scene, ""), fingerprint);
+        render_progressive(&scene, &mut accumulator, 16, &|_| {}, |_| Ok::<(), ()>(())).unwrap();
+        assert_eq!(accumulator.samples_per_pixel(), 12);
+        assert_eq!(accumulator.passes, 2);
+
+        // while other changes do not
+        let source = SCENE.replace("max_depth = 2", "max_depth = 3");
+        let scene = Scene::parse(&source).unwrap();
+        assert_ne!(checkpoint_fingerprint(&source, &scene, ""), fingerprint);
+        assert_ne!(
+            checkpoint_fingerprint(SCENE, &scene, "--integrator normals"),
+            fingerprint
+        );
+    }
+
+    #[test]
+    fn adaptive_progress() {
+        // a flat background that converges at once, and a tiny sphere whose
+        // edge never does
+        let source = r#"
+background = [0.5, 0.5, 0.5]
+
+[image]
+width = 64
+height = 64
+samples_per_pixel = 400
+max_depth = 2
+adaptive = { threshold = 1e-9, min_samples = 16 }
+
+[camera]
+look_from = [0.0, 0.0, 1.0]
+look_at = [0.0, 0.0, 0.0]
+vfov = 90.0
+
+[materials.matte]
+type = "lambertian"
+albedo = [0.5, 0.5, 0.5]
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 0.01
+material = "matte"
+"#;
+        let scene = Scene::parse(source).unwrap();
+        let mut accumulator = Accumulator::new(scene.width, scene.height, 0);
+
+        let fraction = AtomicU64::new(0);
+        let progress = |progress: &Progress| {
+            assert!((0. ..=1.).contains(&progress.fraction()));
+            fraction.fetch_max(progress.fraction().to_bits(), Ordering::Relaxed);
+        };
+        render_progressive(
+            &scene,
+            &mut accumulator,
+            16,
+            &progress,
+            |_| Ok::<(), ()>(()),
+        )
+        .unwrap();
+
+        // the handful of noisy pixels do not get the rest of the budget
+        assert_eq!(accumulator.passes, 1);
+        assert!(f64::from_bits(fraction.into_inner()) < 0.1);
+    }
+
+    #[test]
+    fn reproducible() {
+        let source = format!(
+            "{SCENE}{}",
+            r#"
+[materials.matte]
+type = "lambertian"
+albedo = [0.5, 0.5, 0.5]
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, -1.0]
+radius = 0.5
+material = "matte"
+"#
+        );
+        let scene = Scene::parse(&source).unwrap();
+        let render = |seed, samples_per_pass| {
+            let mut accumulator = Accumulator::new(scene.width, scene.height, seed);
+            render_progressive(&scene, &mut accumulator, samples_per_pass, &|_| {}, |_| {
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+            accumulator.framebuffer().pixels().to_vec()
+        };
+
+        // the same seed gives the same image, however the samples are split up
+        assert_eq!(render(3, 5), render(3, 5));
+        assert_eq!(render(3, 1), render(3, 5));
+        assert_ne!(render(3, 5), render(4, 5));
+    }
 }
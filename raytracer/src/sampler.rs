@@ -0,0 +1,416 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// largest f64 below 1, so that samples stay in [0, 1)
+const ONE_MINUS_EPSILON: f64 = 1. - f64::EPSILON / 2.;
+
+/// Hands out the random numbers of one pixel sample at a time, dimension by
+/// dimension: the pixel position, the lens, the shutter time and every bounce
+/// each take the next one or two dimensions. Low-discrepancy samplers spread the
+/// samples of a pixel evenly over every dimension, so images converge faster
+/// than with independent random numbers.
+pub trait Sampler {
+    /// start drawing the dimensions of sample `index` of pixel (x, y)
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: u32);
+
+    /// next dimension, in [0, 1)
+    fn get_1d(&mut self) -> f64;
+
+    /// next two dimensions, in [0, 1)²
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+/// The kinds of samplers, to build one for every thread
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum SamplerKind {
+    /// uncorrelated random numbers
+    Independent,
+    /// one jittered sample in each of `samples_per_pixel` strata, per dimension
+    Stratified,
+    /// the Halton sequence, randomly shifted for every pixel
+    Halton,
+    /// Owen-scrambled Sobol points, shuffled per pair of dimensions
+    #[default]
+    Sobol,
+}
+
+impl SamplerKind {
+    /// Sampler whose numbers only depend on the `seed`, the pixel, the index of
+    /// the sample and the dimension, so renders are reproducible whichever
+    /// thread renders a sample
+    pub fn build(self, seed: u64, samples_per_pixel: usize) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(seed, samples_per_pixel)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+/// Where the sampler is in the sequence of one pixel
+#[derive(Clone, Copy, Default)]
+struct Position {
+    /// hash of the seed and the pixel
+    pixel: u64,
+    index: u32,
+    dimension: u32,
+}
+
+impl Position {
+    fn new(seed: u64, x: usize, y: usize, index: u32) -> Self {
+        Self {
+            pixel: hash(&[seed, x as u64, y as u64]),
+            index,
+            dimension: 0,
+        }
+    }
+
+    /// the next `n` dimensions start at the one returned
+    fn take(&mut self, n: u32) -> u32 {
+        let dimension = self.dimension;
+        self.dimension += n;
+        dimension
+    }
+}
+
+pub struct IndependentSampler {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: u32) {
+        self.rng = StdRng::seed_from_u64(hash(&[self.seed, x as u64, y as u64, index as u64]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.gen()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.gen(), self.rng.gen())
+    }
+}
+
+pub struct StratifiedSampler {
+    seed: u64,
+    /// strata per dimension, the samples of every round of that many samples
+    /// fall in different strata
+    strata: u32,
+    position: Position,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: usize) -> Self {
+        Self {
+            seed,
+            strata: samples_per_pixel.clamp(1, u32::MAX as usize) as u32,
+            position: Position::default(),
+        }
+    }
+
+    /// stratum of the current sample among `count`, shuffled differently for
+    /// every dimension and round
+    fn stratum(&self, dimension: u32, count: u32) -> u32 {
+        let Position { pixel, index, .. } = self.position;
+        let round = index / self.strata;
+        let permutation = hash(&[pixel, dimension as u64, round as u64]) as u32;
+
+        permutation_element(index % self.strata % count, count, permutation)
+    }
+
+    /// random offset within the stratum
+    fn jitter(&self, dimension: u32) -> f64 {
+        let Position { pixel, index, .. } = self.position;
+        to_unit(hash(&[pixel, dimension as u64, index as u64, 1]) as u32)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: u32) {
+        self.position = Position::new(self.seed, x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.position.take(1);
+        let stratum = self.stratum(dimension, self.strata);
+
+        ((stratum as f64 + self.jitter(dimension)) / self.strata as f64).min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let dimension = self.position.take(2);
+
+        // a square grid of at least as many strata as samples
+        let side = (self.strata as f64).sqrt().ceil() as u32;
+        let stratum = self.stratum(dimension, side * side);
+        let (x, y) = (stratum % side, stratum / side);
+
+        (
+            ((x as f64 + self.jitter(dimension)) / side as f64).min(ONE_MINUS_EPSILON),
+            ((y as f64 + self.jitter(dimension + 1)) / side as f64).min(ONE_MINUS_EPSILON),
+        )
+    }
+}
+
+/// bases of the Halton dimensions, later dimensions are random
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+pub struct HaltonSampler {
+    seed: u64,
+    position: Position,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            position: Position::default(),
+        }
+    }
+
+    fn sample(&mut self) -> f64 {
+        let Position { pixel, index, .. } = self.position;
+        let dimension = self.position.take(1);
+        let shift = to_unit(hash(&[pixel, dimension as u64]) as u32);
+
+        match PRIMES.get(dimension as usize) {
+            // Cranley-Patterson rotation, so that pixels do not share their samples
+            Some(&base) => (radical_inverse(base, index as u64) + shift).fract(),
+            None => to_unit(hash(&[pixel, dimension as u64, index as u64]) as u32),
+        }
+        .min(ONE_MINUS_EPSILON)
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: u32) {
+        self.position = Position::new(self.seed, x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.sample()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.sample(), self.sample())
+    }
+}
+
+/// digits of `index` in `base`, mirrored around the decimal point
+fn radical_inverse(base: u32, mut index: u64) -> f64 {
+    let base = base as u64;
+    let mut reversed = 0;
+    let mut scale = 1.;
+
+    while index > 0 {
+        reversed = reversed * base + index % base;
+        scale /= base as f64;
+        index /= base;
+    }
+
+    reversed as f64 * scale
+}
+
+/// Owen-scrambled Sobol points as in Burley's "Practical Hash-based Owen
+/// Scrambling" (2020): every pair of dimensions uses the first two Sobol
+/// dimensions, with its own shuffle of the sample order and its own scramble
+pub struct SobolSampler {
+    seed: u64,
+    position: Position,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            position: Position::default(),
+        }
+    }
+
+    /// shuffled index and scrambling seed of the next dimensions
+    fn next(&mut self, n: u32) -> (u32, u64) {
+        let Position { pixel, index, .. } = self.position;
+        let dimension = self.position.take(n);
+        let seed = hash(&[pixel, dimension as u64]);
+
+        (nested_uniform_scramble(index, seed as u32), seed >> 32)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: u32) {
+        self.position = Position::new(self.seed, x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (index, seed) = self.next(1);
+
+        to_unit(nested_uniform_scramble(index.reverse_bits(), seed as u32))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (index, seed) = self.next(2);
+        let (x, y) = sobol_2d(index);
+
+        (
+            to_unit(nested_uniform_scramble(x, seed as u32)),
+            to_unit(nested_uniform_scramble(y, hash(&[seed]) as u32)),
+        )
+    }
+}
+
+/// the first two dimensions of the Sobol sequence, as 32-bit fractions
+fn sobol_2d(index: u32) -> (u32, u32) {
+    let mut y = 0;
+    let mut direction = 1 << 31;
+    for bit in 0..32 {
+        if index & (1 << bit) != 0 {
+            y ^= direction;
+        }
+        direction ^= direction >> 1;
+    }
+
+    (index.reverse_bits(), y)
+}
+
+/// random permutation of the binary digits of `x`, where each digit flips
+/// depending on the digits above it (Laine and Karras' hash)
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    let mut x = x.reverse_bits();
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+
+    x.reverse_bits()
+}
+
+/// element `i` of a random permutation of 0..`count` (Kensler, "Correlated
+/// Multi-Jittered Sampling", 2013)
+fn permutation_element(mut i: u32, count: u32, p: u32) -> u32 {
+    let mut w = count - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+
+        if i < count {
+            return (i.wrapping_add(p)) % count;
+        }
+    }
+}
+
+/// 32-bit fraction as a number in [0, 1)
+fn to_unit(x: u32) -> f64 {
+    x as f64 / (1u64 << 32) as f64
+}
+
+/// scrambles the values into one unrelated to nearby inputs
+fn hash(values: &[u64]) -> u64 {
+    values
+        .iter()
+        .fold(0x2545f4914f6cdd1d, |h, &v| splitmix64(h ^ splitmix64(v)))
+}
+
+/// SplitMix64 finalizer, scrambles nearby inputs into unrelated outputs
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// whether each of the `n` intervals of [0, 1) holds one of the values
+    fn covers(values: impl Iterator<Item = f64>, n: usize) -> bool {
+        let mut seen = vec![false; n];
+        for value in values {
+            assert!((0. ..1.).contains(&value));
+            seen[(value * n as f64) as usize] = true;
+        }
+
+        seen.into_iter().all(|s| s)
+    }
+
+    #[test]
+    fn low_discrepancy() {
+        let draw = |kind: SamplerKind, n: u32| {
+            let mut sampler = kind.build(7, n as usize);
+            (0..n)
+                .map(|index| {
+                    sampler.start_pixel_sample(3, 5, index);
+                    (sampler.get_1d(), sampler.get_2d())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let stratified = draw(SamplerKind::Stratified, 16);
+        assert!(covers(stratified.iter().map(|s| s.0), 16));
+
+        // Sobol points are stratified along every axis at once
+        let sobol = draw(SamplerKind::Sobol, 16);
+        assert!(covers(sobol.iter().map(|s| s.0), 16));
+        assert!(covers(sobol.iter().map(|s| s.1 .0), 16));
+        assert!(covers(sobol.iter().map(|s| s.1 .1), 16));
+
+        assert_eq!(radical_inverse(2, 6), 0.375);
+        assert!((radical_inverse(3, 5) - 7. / 9.).abs() < 1e-12);
+
+        // the same sample always gets the same numbers
+        for kind in [
+            SamplerKind::Independent,
+            SamplerKind::Stratified,
+            SamplerKind::Halton,
+            SamplerKind::Sobol,
+        ] {
+            let mut sampler = kind.build(1, 4);
+            let mut draw = |index| {
+                sampler.start_pixel_sample(0, 0, index);
+                (sampler.get_1d(), sampler.get_2d(), sampler.get_1d())
+            };
+            let first = draw(2);
+            assert_eq!(draw(2), first, "{kind:?}");
+            assert_ne!(draw(3), first, "{kind:?}");
+        }
+    }
+}
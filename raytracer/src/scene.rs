@@ -1,12 +1,23 @@
 use crate::{
-    camera::Camera,
+    accumulator::AdaptiveSampling,
+    animation::Animation,
+    bvh::Bvh,
+    camera::{Camera, CameraModel},
     color::Color,
+    denoise::Denoiser,
+    display::DisplayTransform,
+    hittable_list::{HittableList, SharedHittable},
+    integrator::{Integrator, PathTracer},
+    light::Light,
     material::{Dielectric, Lambertian, Material, Metal},
-    point3d::{Length, Point3D},
+    plane::Plane,
+    point3d::{Length, Normalize, Point3D},
+    ray::Ray,
+    sampler::SamplerKind,
     sphere::Sphere,
 };
 
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 pub struct Scene {
     // image
@@ -14,11 +25,46 @@ pub struct Scene {
     pub width: usize,
     pub height: usize,
     pub samples_per_pixel: usize,
+    /// spend the samples where the image is still noisy
+    pub adaptive: Option<AdaptiveSampling>,
+    /// where in the pixel, on the lens and over the bounces samples go
+    pub sampler: SamplerKind,
     pub depth_max: isize,
     // camera
-    pub camera: Camera,
+    pub camera: Box<dyn CameraModel>,
     // world
-    pub objects: Vec<Sphere>,
+    pub objects: Bvh<SharedHittable>,
+    pub background: Background,
+    pub lights: Vec<Light>,
+    // rendering
+    pub integrator: Box<dyn Integrator>,
+    pub display: DisplayTransform,
+    /// smooths the noise of the finished image
+    pub denoiser: Option<Denoiser>,
+    /// moves the camera and objects from frame to frame
+    pub animation: Option<Animation>,
+}
+
+/// What a ray sees when it leaves the scene without hitting anything
+#[derive(Clone, Copy)]
+pub enum Background {
+    /// white to light blue gradient from the horizon upwards
+    Sky,
+    /// uniform color, black for scenes that are only lit by their lights
+    Color(Color),
+}
+
+impl Background {
+    pub fn color(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Sky => {
+                let unit_direction = ray.direction.normalize();
+                let t = 0.5 * (unit_direction[1] + 1.0);
+                (1.0 - t as f32) * Color(1., 1., 1.) + t as f32 * Color(0.5, 0.7, 1.0)
+            }
+            Background::Color(color) => *color,
+        }
+    }
 }
 
 impl Scene {
@@ -48,21 +94,76 @@ impl Scene {
             width: image_width,
             height: image_height,
             samples_per_pixel,
+            adaptive: None,
+            sampler: SamplerKind::default(),
             depth_max,
-            camera,
-            objects: random_world(),
+            camera: Box::new(camera),
+            objects: Bvh::new(random_world(0).objects),
+            background: Background::Sky,
+            lights: Vec::new(),
+            integrator: Box::new(PathTracer),
+            display: DisplayTransform::default(),
+            denoiser: None,
+            animation: None,
         }
     }
 }
 
-fn random_world() -> Vec<Sphere> {
-    let mut scene = vec![
-        Sphere {
-            // ground
-            center: Point3D::new(0., -1000., -1.),
-            radius: 1000.,
-            material: Material::Lambertian(Lambertian::new(Color(0.5, 0.5, 0.5))),
-        },
+/// names of the scenes that need no scene file
+pub const BUILT_IN_SCENES: [&str; 1] = ["random_world"];
+
+impl Scene {
+    /// the built-in scene called `name`, one of `BUILT_IN_SCENES`
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "random_world" => Some(Self::new()),
+            _ => None,
+        }
+    }
+
+    /// Change the image size. With only one side given, the other follows
+    /// from the aspect ratio; with both, the view widens or narrows to the
+    /// new aspect ratio and keeps its vertical field of view.
+    pub fn resize(&mut self, width: Option<usize>, height: Option<usize>) {
+        match (width, height) {
+            (Some(width), Some(height)) => {
+                let aspect_ratio = width as f64 / height as f64;
+                self.camera.stretch(aspect_ratio / self.aspect_ratio);
+                self.aspect_ratio = aspect_ratio;
+                self.width = width;
+                self.height = height;
+            }
+            (Some(width), None) => {
+                self.width = width;
+                self.height = ((width as f64 / self.aspect_ratio) as usize).max(1);
+            }
+            (None, Some(height)) => {
+                self.width = ((height as f64 * self.aspect_ratio) as usize).max(1);
+                self.height = height;
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The cover of Ray Tracing in One Weekend, the small spheres are placed by
+/// a random number generator started from `seed`
+pub fn random_world(seed: u64) -> HittableList {
+    let mut scene = HittableList::new();
+    // ground
+    scene.add(Plane::new(
+        Point3D::new(0., 0., 0.),
+        Point3D::new(0., 1., 0.),
+        Material::Lambertian(Lambertian::new(Color(0.5, 0.5, 0.5))),
+    ));
+
+    for sphere in [
         Sphere {
             center: Point3D::new(0., 1., 0.),
             radius: 1.,
@@ -78,9 +179,11 @@ fn random_world() -> Vec<Sphere> {
             radius: 1.,
             material: Material::Metal(Metal::new(Color(0.7, 0.6, 0.5), 0.)),
         },
-    ];
+    ] {
+        scene.add(sphere);
+    }
 
-    let mut rng = rand::thread_rng();
+    let mut rng = StdRng::seed_from_u64(seed);
     for a in -11..11 {
         for b in -11..11 {
             let choose_material = rng.gen::<f64>();
@@ -92,25 +195,25 @@ fn random_world() -> Vec<Sphere> {
 
             if (center - Point3D::new(4., 0.2, 0.)).length() > 0.9 {
                 if choose_material < 0.8 {
-                    let albedo = Color::random() * Color::random();
+                    let albedo = Color::random(&mut rng) * Color::random(&mut rng);
                     // diffuse
-                    scene.push(Sphere::new(
+                    scene.add(Sphere::new(
                         center,
                         0.2,
                         Material::Lambertian(Lambertian::new(albedo)),
                     ));
                 } else if choose_material < 0.95 {
                     // metal
-                    let albedo = Color::random_range(0.5, 1.);
+                    let albedo = Color::random_range(0.5, 1., &mut rng);
                     let fuzz = rng.gen_range(0.0..0.5);
-                    scene.push(Sphere::new(
+                    scene.add(Sphere::new(
                         center,
                         0.2,
                         Material::Metal(Metal::new(albedo, fuzz)),
                     ));
                 } else {
                     // glass
-                    scene.push(Sphere::new(
+                    scene.add(Sphere::new(
                         center,
                         0.2,
                         Material::Dielectric(Dielectric::new(1.5)),
@@ -0,0 +1,1783 @@
+//! TOML scene description
+//!
+//! ```toml
+//! [image]
+//! width = 400
+//! aspect_ratio = 1.5
+//! samples_per_pixel = 100
+//! max_depth = 50
+//! # optional: stop sampling pixels once their relative error is below
+//! # `threshold`, after at least `min_samples`, and give their samples to
+//! # noisier pixels, `samples_per_pixel` is then the average
+//! adaptive = { threshold = 0.02, min_samples = 16 }
+//! # "sobol" (default), "halton", "stratified" or "independent"
+//! sampler = "sobol"
+//!
+//! # "perspective" (default), "orthographic" (with the `height` of the view
+//! # instead of `vfov`), an equidistant "fisheye" (`vfov` across the image
+//! # height) or a 360° "equirectangular" panorama; only perspective cameras
+//! # have an `aperture` and `focus_distance`
+//! [camera]
+//! type = "perspective"
+//! look_from = [13.0, 2.0, 3.0]
+//! look_at = [0.0, 0.0, 0.0]
+//! vfov = 20.0
+//! aperture = 0.1
+//! focus_distance = 10.0
+//!
+//! # "sky" (default) or a color
+//! background = [0.0, 0.0, 0.0]
+//!
+//! # "path_tracing" (default), "whitted" (lit by the [[lights]]),
+//! # "ambient_occlusion" (with `samples` and `distance`), or the debug
+//! # views "normals", "depth" (with `far`) and "hit_miss"
+//! [integrator]
+//! type = "path_tracing"
+//!
+//! # deprecated: instead of an [integrator], "path_tracing" or
+//! # "direct_lighting" for the Whitted integrator
+//! shading = "direct_lighting"
+//!
+//! # how radiance becomes 8-bit colors: an exposure in stops, a tone map
+//! # ("clamp" (default), "reinhard" or "aces") and a transfer function
+//! # ("gamma2" (default), "srgb" or "linear")
+//! [display]
+//! exposure = 0.0
+//! tone_map = "aces"
+//! transfer = "srgb"
+//!
+//! # optional: smooth the noise of the finished image, guided by the albedo,
+//! # normals and depth of the first hits, all settings can be left out
+//! [denoise]
+//! iterations = 5
+//! sigma_color = 4.0
+//! sigma_normal = 0.3
+//! sigma_albedo = 0.1
+//! sigma_depth = 0.1
+//!
+//! [materials.ground]
+//! type = "lambertian"
+//! albedo = [0.5, 0.5, 0.5]
+//!
+//! [[objects]]
+//! type = "sphere"
+//! center = [0.0, -1000.0, 0.0]
+//! radius = 1000.0
+//! material = "ground"
+//!
+//! [[objects]]
+//! type = "mesh"
+//! path = "model.obj" # relative to the scene file
+//! material = "ground"
+//! ```
+//!
+//! Material types are `lambertian` (`albedo`, and optionally a Phong `specular`
+//! exponent and a `reflective` coefficient for Whitted ray tracing), `metal`
+//! (`albedo` and `fuzz`), `dielectric` (`refraction_index`), `diffuse_light`
+//! (`emit`, white by default; also called `light`) and `isotropic` (`albedo`),
+//! the phase function of volumes.
+//!
+//! An `albedo` is either a color or a texture: `checker` (`even` and `odd`
+//! colors or textures, alternating in cubes of size `scale`), Perlin `noise`,
+//! `turbulence` or `marble` (with a frequency `scale`), or `image` (a `path`
+//! relative to the scene file, mapped onto the surface coordinates):
+//!
+//! ```toml
+//! [materials.floor]
+//! type = "lambertian"
+//! albedo = { type = "checker", even = [0.2, 0.3, 0.1], odd = [0.9, 0.9, 0.9], scale = 2.0 }
+//! ```
+//!
+//! Lights are only used by the Whitted integrator:
+//!
+//! ```toml
+//! [[lights]]
+//! type = "ambient"
+//! intensity = 0.2
+//!
+//! [[lights]]
+//! type = "point" # or "directional" with a `direction` towards the light
+//! intensity = 0.6
+//! position = [2.0, 1.0, 0.0]
+//! ```
+//!
+//! Object types are `sphere`, `triangle` (three `vertices`), `mesh`, `plane`
+//! (`point` and `normal`), `xy_rect` (`x` and `y` ranges at depth `z`, and
+//! likewise `xz_rect` and `yz_rect`) and `box` (`min` and `max` corners).
+//! Every object can be placed with an optional transform, applied as scale,
+//! rotation (degrees around x, then y, then z) and translation:
+//!
+//! ```toml
+//! [[objects]]
+//! type = "mesh"
+//! path = "model.obj"
+//! material = "ground"
+//! transform = { translate = [2.0, 0.0, 0.0], rotate = [0.0, 45.0, 0.0], scale = 0.5 }
+//! ```
+//!
+//! Instead of a `vfov` and `aperture`, cameras can be given a lens as on a
+//! 35mm camera, in scene units of metres: a `focal_length` and the
+//! `sensor_width` in millimetres (default 36, full frame), and an `f_number`.
+//! With `autofocus` on a pixel (column and row from the top left), the
+//! camera focuses on whatever that pixel sees, or at the `focus_distance` if it
+//! sees nothing. An `exposure_time` in seconds at an `iso` (default 100)
+//! exposes the image like a real camera would, for radiance in cd/m², on top
+//! of the `[display]` exposure:
+//!
+//! ```toml
+//! [camera]
+//! look_from = [13.0, 2.0, 3.0]
+//! look_at = [0.0, 0.0, 0.0]
+//! focal_length = 85.0
+//! f_number = 1.8
+//! autofocus = [200, 150]
+//! exposure_time = 0.5
+//! iso = 800
+//! ```
+//!
+//! For motion blur, the camera's `shutter = [open, close]` times (default
+//! `[0.0, 0.0]`) are sampled for every ray. A `moving_sphere` travels from
+//! `center0` to `center1`, and any object can be given a `motion` that
+//! translates it after its transform, both over a `time` interval that
+//! defaults to `[0.0, 1.0]`:
+//!
+//! ```toml
+//! [[objects]]
+//! type = "box"
+//! min = [0.0, 0.0, 0.0]
+//! max = [1.0, 1.0, 1.0]
+//! material = "ground"
+//! motion = { translate = [0.0, 0.5, 0.0] }
+//! ```
+//!
+//! An `[animation]` renders a range of `frames` (first and last) as an image
+//! sequence. Keyframes move the camera and objects: every `[[animation.camera]]`
+//! key sets any of `look_from`, `look_at`, `vfov` and `focus_distance` at a
+//! `frame`, and every key in an object's `keyframes` any of the `translate`,
+//! `rotate` and `scale` of its transform. Values without keys stay as given in
+//! `[camera]` and `transform`. In between keys the values are interpolated
+//! linearly, or with `interpolation = "spline"` along smooth curves. An object
+//! whose scale passes through zero between two keys is left out of the frames
+//! where it is flat.
+//!
+//! ```toml
+//! [animation]
+//! frames = [1, 96]
+//! interpolation = "spline"
+//!
+//! [[animation.camera]]
+//! frame = 1
+//! look_from = [13.0, 2.0, 3.0]
+//!
+//! [[animation.camera]]
+//! frame = 96
+//! look_from = [3.0, 2.0, 13.0]
+//! vfov = 30.0
+//!
+//! [[objects]]
+//! type = "box"
+//! min = [-0.5, 0.0, -0.5]
+//! max = [0.5, 1.0, 0.5]
+//! material = "ground"
+//! keyframes = [
+//!     { frame = 1, rotate = [0.0, 0.0, 0.0] },
+//!     { frame = 96, rotate = [0.0, 360.0, 0.0] },
+//! ]
+//! ```
+//!
+//! Giving an object a `density` fills its (convex) shape with fog or smoke of
+//! that density, scattering with its `isotropic` material:
+//!
+//! ```toml
+//! [[objects]]
+//! type = "sphere"
+//! center = [0.0, 1.0, 0.0]
+//! radius = 1.0
+//! material = "smoke"
+//! density = 0.5
+//! ```
+
+use std::{collections::HashMap, fmt, ops::Range, path::Path, sync::Arc};
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+    aarect::{XyRect, XzRect, YzRect},
+    accumulator::AdaptiveSampling,
+    animation::{AnimatedObject, Animation, CameraRig, Interpolation, Track, TransformTrack},
+    bvh::Bvh,
+    camera::{Lens, Projection},
+    color::Color,
+    cuboid::Cuboid,
+    denoise::Denoiser,
+    display::{DisplayTransform, ToneMap, Transfer},
+    hittable_list::SharedHittable,
+    integrator::{AmbientOcclusion, DebugMode, DebugView, Integrator, PathTracer, Whitted},
+    light::Light,
+    material::{Dielectric, DiffuseLight, Isotropic, Lambertian, Material, Metal},
+    matrix::Matrix4,
+    medium::ConstantMedium,
+    mesh::TriangleMesh,
+    moving::Moving,
+    plane::Plane,
+    point3d::Point3D,
+    sampler::SamplerKind,
+    scene::{Background, Scene},
+    sphere::{MovingSphere, Sphere},
+    texture::{Checker, ImageTexture, Noise, NoiseKind, SharedTexture, SolidColor},
+    transform::Transformed,
+    triangle::Triangle,
+};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl SceneError {
+    fn at(source: &str, span: Option<Range<usize>>, message: impl Into<String>) -> Self {
+        let (line, column) = span.map_or((0, 0), |span| line_column(source, span.start));
+
+        SceneError::Parse {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "could not read scene file: {e}"),
+            SceneError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {line}, column {column}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(e: std::io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+/// 1-based line and column of a byte offset
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+
+    (line, column)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+    image: Spanned<ImageDescription>,
+    camera: Spanned<CameraDescription>,
+    #[serde(default)]
+    background: BackgroundDescription,
+    integrator: Option<IntegratorDescription>,
+    /// what `integrator` used to be
+    shading: Option<Spanned<ShadingDescription>>,
+    #[serde(default)]
+    display: DisplayDescription,
+    denoise: Option<DenoiseDescription>,
+    animation: Option<Spanned<AnimationDescription>>,
+    #[serde(default)]
+    lights: Vec<LightDescription>,
+    #[serde(default)]
+    materials: HashMap<String, Spanned<MaterialDescription>>,
+    #[serde(default)]
+    objects: Vec<Spanned<ObjectDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImageDescription {
+    width: usize,
+    height: Option<usize>,
+    aspect_ratio: Option<f64>,
+    samples_per_pixel: usize,
+    max_depth: isize,
+    adaptive: Option<AdaptiveDescription>,
+    #[serde(default)]
+    sampler: SamplerDescription,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum SamplerDescription {
+    Independent,
+    Stratified,
+    Halton,
+    #[default]
+    Sobol,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AdaptiveDescription {
+    threshold: f64,
+    #[serde(default = "default_min_samples")]
+    min_samples: u32,
+}
+
+fn default_min_samples() -> u32 {
+    16
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    #[serde(default, rename = "type")]
+    projection: ProjectionDescription,
+    look_from: [f64; 3],
+    look_at: [f64; 3],
+    #[serde(default = "default_vup")]
+    vup: [f64; 3],
+    /// perspective and fisheye cameras only
+    vfov: Option<f64>,
+    /// of the view of orthographic cameras, in world units
+    height: Option<f64>,
+    #[serde(default)]
+    aperture: f64,
+    focus_distance: Option<f64>,
+    /// in millimetres, instead of `vfov`
+    focal_length: Option<f64>,
+    #[serde(default = "default_sensor_width")]
+    sensor_width: f64,
+    /// instead of `aperture`
+    f_number: Option<f64>,
+    /// pixel to focus on, column and row from the top left
+    autofocus: Option<[f64; 2]>,
+    /// in seconds
+    exposure_time: Option<f64>,
+    iso: Option<f64>,
+    /// times at which the shutter opens and closes
+    #[serde(default)]
+    shutter: [f64; 2],
+}
+
+fn default_sensor_width() -> f64 {
+    36.
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ProjectionDescription {
+    #[default]
+    Perspective,
+    Orthographic,
+    Fisheye,
+    Equirectangular,
+}
+
+fn default_vup() -> [f64; 3] {
+    [0., 1., 0.]
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnimationDescription {
+    /// first and last frame
+    frames: [u32; 2],
+    #[serde(default)]
+    interpolation: InterpolationDescription,
+    #[serde(default)]
+    camera: Vec<CameraKeyDescription>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum InterpolationDescription {
+    #[default]
+    Linear,
+    Spline,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraKeyDescription {
+    frame: f64,
+    look_from: Option<[f64; 3]>,
+    look_at: Option<[f64; 3]>,
+    vfov: Option<f64>,
+    focus_distance: Option<f64>,
+}
+
+/// either `"sky"` or a color
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BackgroundDescription {
+    Sky(SkyDescription),
+    Color([f32; 3]),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SkyDescription {
+    Sky,
+}
+
+impl Default for BackgroundDescription {
+    fn default() -> Self {
+        BackgroundDescription::Sky(SkyDescription::Sky)
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum IntegratorDescription {
+    #[default]
+    PathTracing,
+    Whitted,
+    AmbientOcclusion {
+        #[serde(default = "default_occlusion_samples")]
+        samples: usize,
+        #[serde(default = "default_occlusion_distance")]
+        distance: f64,
+    },
+    Normals,
+    Depth {
+        far: f64,
+    },
+    HitMiss,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ShadingDescription {
+    PathTracing,
+    DirectLighting,
+}
+
+impl ShadingDescription {
+    /// the integrator that took over the shading mode
+    fn integrator(self) -> IntegratorDescription {
+        match self {
+            ShadingDescription::PathTracing => IntegratorDescription::PathTracing,
+            ShadingDescription::DirectLighting => IntegratorDescription::Whitted,
+        }
+    }
+}
+
+fn default_occlusion_samples() -> usize {
+    16
+}
+
+fn default_occlusion_distance() -> f64 {
+    f64::INFINITY
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct DisplayDescription {
+    #[serde(default)]
+    exposure: f32,
+    #[serde(default)]
+    tone_map: ToneMapDescription,
+    #[serde(default)]
+    transfer: TransferDescription,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DenoiseDescription {
+    iterations: Option<u32>,
+    sigma_color: Option<f32>,
+    sigma_normal: Option<f32>,
+    sigma_albedo: Option<f32>,
+    sigma_depth: Option<f32>,
+}
+
+impl DenoiseDescription {
+    fn build(&self) -> Denoiser {
+        let default = Denoiser::default();
+
+        Denoiser {
+            iterations: self.iterations.unwrap_or(default.iterations),
+            sigma_color: self.sigma_color.unwrap_or(default.sigma_color),
+            sigma_normal: self.sigma_normal.unwrap_or(default.sigma_normal),
+            sigma_albedo: self.sigma_albedo.unwrap_or(default.sigma_albedo),
+            sigma_depth: self.sigma_depth.unwrap_or(default.sigma_depth),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ToneMapDescription {
+    #[default]
+    Clamp,
+    Reinhard,
+    Aces,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum TransferDescription {
+    Linear,
+    #[default]
+    Gamma2,
+    Srgb,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum LightDescription {
+    Ambient { intensity: f64 },
+    Directional { intensity: f64, direction: [f64; 3] },
+    Point { intensity: f64, position: [f64; 3] },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDescription {
+    Lambertian {
+        albedo: TextureDescription,
+        specular: Option<f64>,
+        #[serde(default)]
+        reflective: f32,
+    },
+    Metal {
+        albedo: TextureDescription,
+        fuzz: f64,
+    },
+    Dielectric {
+        refraction_index: f64,
+    },
+    /// `light` is its name from before it emitted anything
+    #[serde(alias = "light")]
+    DiffuseLight {
+        #[serde(default = "default_emit")]
+        emit: [f32; 3],
+    },
+    Isotropic {
+        albedo: TextureDescription,
+    },
+}
+
+/// either a color or a table with the texture `type`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TextureDescription {
+    Color([f32; 3]),
+    Texture(PatternDescription),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum PatternDescription {
+    Checker {
+        even: Box<TextureDescription>,
+        odd: Box<TextureDescription>,
+        #[serde(default = "default_texture_scale")]
+        scale: f64,
+    },
+    Noise {
+        #[serde(default = "default_texture_scale")]
+        scale: f64,
+    },
+    Turbulence {
+        #[serde(default = "default_texture_scale")]
+        scale: f64,
+    },
+    Marble {
+        #[serde(default = "default_texture_scale")]
+        scale: f64,
+    },
+    Image {
+        path: String,
+    },
+}
+
+fn default_emit() -> [f32; 3] {
+    [1., 1., 1.]
+}
+
+fn default_texture_scale() -> f64 {
+    1.
+}
+
+#[derive(Deserialize)]
+struct ObjectDescription {
+    #[serde(flatten)]
+    shape: ShapeDescription,
+    material: Spanned<String>,
+    transform: Option<TransformDescription>,
+    motion: Option<MotionDescription>,
+    #[serde(default)]
+    keyframes: Vec<TransformKeyDescription>,
+    /// turns the shape into the boundary of a constant-density volume
+    density: Option<Spanned<f64>>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ShapeDescription {
+    Sphere {
+        center: [f64; 3],
+        radius: f64,
+    },
+    MovingSphere {
+        center0: [f64; 3],
+        center1: [f64; 3],
+        radius: f64,
+        #[serde(default = "default_motion_time")]
+        time: [f64; 2],
+    },
+    Triangle {
+        vertices: [[f64; 3]; 3],
+    },
+    /// Wavefront OBJ file
+    Mesh {
+        path: String,
+    },
+    Plane {
+        point: [f64; 3],
+        normal: [f64; 3],
+    },
+    XyRect {
+        x: [f64; 2],
+        y: [f64; 2],
+        z: f64,
+    },
+    XzRect {
+        x: [f64; 2],
+        z: [f64; 2],
+        y: f64,
+    },
+    YzRect {
+        y: [f64; 2],
+        z: [f64; 2],
+        x: f64,
+    },
+    #[serde(rename = "box")]
+    Cuboid {
+        min: [f64; 3],
+        max: [f64; 3],
+    },
+}
+
+/// Scaled first, then rotated around the x, y and z axes, then translated
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDescription {
+    #[serde(default)]
+    translate: [f64; 3],
+    /// degrees around the x, y and z axes
+    #[serde(default)]
+    rotate: [f64; 3],
+    scale: Option<ScaleDescription>,
+}
+
+/// Any of the parts of the transform at a `frame`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformKeyDescription {
+    frame: f64,
+    translate: Option<[f64; 3]>,
+    rotate: Option<[f64; 3]>,
+    scale: Option<ScaleDescription>,
+}
+
+/// Translated by `translate` over the `time` interval, after the transform
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MotionDescription {
+    translate: [f64; 3],
+    #[serde(default = "default_motion_time")]
+    time: [f64; 2],
+}
+
+fn default_motion_time() -> [f64; 2] {
+    [0., 1.]
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScaleDescription {
+    Uniform(f64),
+    PerAxis([f64; 3]),
+}
+
+impl ScaleDescription {
+    fn factors(&self) -> Point3D {
+        match *self {
+            ScaleDescription::Uniform(s) => point([s; 3]),
+            ScaleDescription::PerAxis(s) => point(s),
+        }
+    }
+}
+
+fn point(p: [f64; 3]) -> Point3D {
+    Point3D::new(p[0], p[1], p[2])
+}
+
+fn color(c: [f32; 3]) -> Color {
+    Color(c[0], c[1], c[2])
+}
+
+impl TextureDescription {
+    fn build(&self, base: &Path) -> Result<SharedTexture, String> {
+        let pattern = match self {
+            TextureDescription::Color(c) => return Ok(Arc::new(SolidColor(color(*c)))),
+            TextureDescription::Texture(pattern) => pattern,
+        };
+
+        Ok(match pattern {
+            PatternDescription::Checker { even, odd, scale } => {
+                Arc::new(Checker::new(even.build(base)?, odd.build(base)?, *scale))
+            }
+            PatternDescription::Noise { scale } => Arc::new(Noise::new(NoiseKind::Smooth, *scale)),
+            PatternDescription::Turbulence { scale } => {
+                Arc::new(Noise::new(NoiseKind::Turbulence, *scale))
+            }
+            PatternDescription::Marble { scale } => Arc::new(Noise::new(NoiseKind::Marble, *scale)),
+            PatternDescription::Image { path } => Arc::new(
+                ImageTexture::open(base.join(path))
+                    .map_err(|e| format!("could not load image `{path}`: {e}"))?,
+            ),
+        })
+    }
+}
+
+impl MaterialDescription {
+    fn build(&self, base: &Path) -> Result<Material, String> {
+        Ok(match self {
+            MaterialDescription::Lambertian {
+                albedo,
+                specular,
+                reflective,
+            } => {
+                let lambertian =
+                    Lambertian::textured(albedo.build(base)?).with_reflective(*reflective);
+                Material::Lambertian(match specular {
+                    Some(specular) => lambertian.with_specular(*specular),
+                    None => lambertian,
+                })
+            }
+            MaterialDescription::Metal { albedo, fuzz } => {
+                Material::Metal(Metal::textured(albedo.build(base)?, *fuzz))
+            }
+            MaterialDescription::Dielectric { refraction_index } => {
+                Material::Dielectric(Dielectric::new(*refraction_index))
+            }
+            MaterialDescription::DiffuseLight { emit } => {
+                Material::DiffuseLight(DiffuseLight::new(color(*emit)))
+            }
+            MaterialDescription::Isotropic { albedo } => {
+                Material::Isotropic(Isotropic::textured(albedo.build(base)?))
+            }
+        })
+    }
+}
+
+impl IntegratorDescription {
+    fn build(&self) -> Box<dyn Integrator> {
+        match *self {
+            IntegratorDescription::PathTracing => Box::new(PathTracer),
+            IntegratorDescription::Whitted => Box::new(Whitted),
+            IntegratorDescription::AmbientOcclusion { samples, distance } => {
+                Box::new(AmbientOcclusion { samples, distance })
+            }
+            IntegratorDescription::Normals => Box::new(DebugView {
+                mode: DebugMode::Normals,
+            }),
+            IntegratorDescription::Depth { far } => Box::new(DebugView {
+                mode: DebugMode::Depth { far },
+            }),
+            IntegratorDescription::HitMiss => Box::new(DebugView {
+                mode: DebugMode::HitMiss,
+            }),
+        }
+    }
+}
+
+impl DisplayDescription {
+    fn build(&self) -> DisplayTransform {
+        DisplayTransform {
+            exposure: self.exposure,
+            tone_map: match self.tone_map {
+                ToneMapDescription::Clamp => ToneMap::Clamp,
+                ToneMapDescription::Reinhard => ToneMap::Reinhard,
+                ToneMapDescription::Aces => ToneMap::Aces,
+            },
+            transfer: match self.transfer {
+                TransferDescription::Linear => Transfer::Linear,
+                TransferDescription::Gamma2 => Transfer::Gamma2,
+                TransferDescription::Srgb => Transfer::Srgb,
+            },
+        }
+    }
+}
+
+impl LightDescription {
+    fn build(&self) -> Light {
+        match *self {
+            LightDescription::Ambient { intensity } => Light::ambient(intensity),
+            LightDescription::Directional {
+                intensity,
+                direction,
+            } => Light::directional(intensity, point(direction)),
+            LightDescription::Point {
+                intensity,
+                position,
+            } => Light::point(intensity, point(position)),
+        }
+    }
+}
+
+impl TransformDescription {
+    fn matrix(&self) -> Matrix4 {
+        Matrix4::placement(point(self.translate), point(self.rotate), self.scale())
+    }
+
+    fn scale(&self) -> Point3D {
+        self.scale
+            .as_ref()
+            .map_or(point([1.; 3]), ScaleDescription::factors)
+    }
+}
+
+impl CameraDescription {
+    /// the lens, for cameras described by their `focal_length`
+    fn lens(&self) -> Result<Option<Lens>, String> {
+        let focal_length = match (self.focal_length, self.f_number) {
+            (Some(focal_length), _) => focal_length,
+            (None, Some(_)) => return Err("`f_number` needs a `focal_length`".to_string()),
+            (None, None) => return Ok(None),
+        };
+        if self.vfov.is_some() {
+            return Err("give either `vfov` or `focal_length`".to_string());
+        }
+        if self.f_number.is_some() && self.aperture != 0. {
+            return Err("give either `aperture` or `f_number`".to_string());
+        }
+        for (name, value) in [
+            ("focal_length", Some(focal_length)),
+            ("sensor_width", Some(self.sensor_width)),
+            ("f_number", self.f_number),
+        ] {
+            if value.is_some_and(|value| value <= 0.) {
+                return Err(format!("`{name}` must be greater than 0"));
+            }
+        }
+
+        Ok(Some(Lens {
+            focal_length,
+            sensor_width: self.sensor_width,
+            // a pinhole without an f-number
+            f_number: self.f_number.unwrap_or(f64::INFINITY),
+        }))
+    }
+
+    /// exposure in stops of the `exposure_time` and `iso`, if there is one
+    fn exposure(&self) -> Result<Option<f32>, String> {
+        let exposure_time = match (self.exposure_time, self.iso) {
+            (Some(exposure_time), _) => exposure_time,
+            (None, Some(_)) => return Err("`iso` needs an `exposure_time`".to_string()),
+            (None, None) => return Ok(None),
+        };
+
+        match (self.lens()?, self.f_number) {
+            (Some(lens), Some(_)) => {
+                Ok(Some(lens.exposure(exposure_time, self.iso.unwrap_or(100.))))
+            }
+            _ => Err("an `exposure_time` needs a `focal_length` and `f_number`".to_string()),
+        }
+    }
+
+    /// the camera over the frames, moved by the `keys` that are given, for an
+    /// image of `width` by `height` pixels
+    fn rig(
+        &self,
+        keys: &[CameraKeyDescription],
+        interpolation: Interpolation,
+        (width, height): (usize, usize),
+        aspect_ratio: f64,
+    ) -> Result<CameraRig, String> {
+        let projection = match self.projection {
+            ProjectionDescription::Perspective => Projection::Perspective,
+            ProjectionDescription::Orthographic => Projection::Orthographic {
+                height: self
+                    .height
+                    .ok_or("an orthographic camera needs a `height`")?,
+            },
+            ProjectionDescription::Fisheye => Projection::Fisheye,
+            ProjectionDescription::Equirectangular => Projection::Equirectangular,
+        };
+
+        let lens = self.lens()?;
+        let fov = match (lens, projection) {
+            // the camera is built from the lens
+            (Some(_), Projection::Perspective) => None,
+            // equidistant: the angle is the distance on the sensor over the focal length
+            (Some(lens), Projection::Fisheye) => {
+                Some((lens.sensor_width / aspect_ratio / lens.focal_length).to_degrees())
+            }
+            (Some(_), _) => {
+                return Err("only perspective and fisheye cameras have a `focal_length`".to_string())
+            }
+            (None, _) => self.vfov,
+        };
+
+        let vfov: Vec<_> = keys
+            .iter()
+            .filter_map(|key| key.vfov.map(|vfov| (key.frame, vfov)))
+            .collect();
+        let perspective_lens = lens.filter(|_| projection == Projection::Perspective);
+        if perspective_lens.is_some() && !vfov.is_empty() {
+            return Err("give either `vfov` keys or a `focal_length`".to_string());
+        }
+        let needs_vfov = matches!(projection, Projection::Perspective | Projection::Fisheye);
+        if needs_vfov && perspective_lens.is_none() && fov.is_none() && vfov.is_empty() {
+            return Err("a perspective or fisheye camera needs a `vfov`".to_string());
+        }
+
+        let autofocus = match self.autofocus {
+            Some(_) if projection != Projection::Perspective => {
+                return Err("only perspective cameras have `autofocus`".to_string())
+            }
+            Some([x, y]) if x < 0. || x >= width as f64 || y < 0. || y >= height as f64 => {
+                return Err(format!("`autofocus` pixel ({x}, {y}) is outside the image"))
+            }
+            Some([x, y]) => Some(((x + 0.5) / width as f64, 1. - (y + 0.5) / height as f64)),
+            None => None,
+        };
+
+        let focus_distance: Vec<_> = keys
+            .iter()
+            .filter_map(|key| key.focus_distance.map(|d| (key.frame, d)))
+            .collect();
+
+        Ok(CameraRig {
+            projection,
+            look_from: track(
+                keys.iter()
+                    .filter_map(|key| key.look_from.map(|p| (key.frame, point(p))))
+                    .collect(),
+                point(self.look_from),
+                interpolation,
+            ),
+            look_at: track(
+                keys.iter()
+                    .filter_map(|key| key.look_at.map(|p| (key.frame, point(p))))
+                    .collect(),
+                point(self.look_at),
+                interpolation,
+            ),
+            vup: point(self.vup),
+            vfov: track(vfov, fov.unwrap_or_default(), interpolation),
+            aperture: self.aperture,
+            lens: perspective_lens,
+            focus_distance: match (focus_distance.is_empty(), self.focus_distance) {
+                (false, _) => Some(Track::new(focus_distance, interpolation)),
+                (true, Some(distance)) => Some(Track::constant(distance)),
+                (true, None) => None,
+            },
+            autofocus,
+            shutter: (self.shutter[0], self.shutter[1]),
+        })
+    }
+}
+
+/// the `keys` of a value, or the same `value` on every frame without any
+fn track<T>(keys: Vec<(f64, T)>, value: T, interpolation: Interpolation) -> Track<T> {
+    if keys.is_empty() {
+        Track::constant(value)
+    } else {
+        Track::new(keys, interpolation)
+    }
+}
+
+/// meshes that were already loaded, by path and material name
+type MeshCache = HashMap<(String, String), Arc<TriangleMesh>>;
+
+impl ObjectDescription {
+    /// the shape, or the volume it bounds, before it is placed
+    fn shape(
+        &self,
+        material: Material,
+        base: &Path,
+        meshes: &mut MeshCache,
+    ) -> Result<SharedHittable, String> {
+        let medium = match (self.density.as_ref().map(Spanned::get_ref), &material) {
+            (Some(&density), Material::Isotropic(isotropic)) => Some((density, isotropic.clone())),
+            (Some(_), _) => {
+                return Err("a volume with a `density` needs an isotropic material".to_string())
+            }
+            (None, _) => None,
+        };
+
+        let object: SharedHittable = match &self.shape {
+            ShapeDescription::Sphere { center, radius } => {
+                Arc::new(Sphere::new(point(*center), *radius, material))
+            }
+            ShapeDescription::MovingSphere {
+                center0,
+                center1,
+                radius,
+                time,
+            } => Arc::new(MovingSphere::new(
+                point(*center0),
+                point(*center1),
+                time[0],
+                time[1],
+                *radius,
+                material,
+            )),
+            ShapeDescription::Triangle { vertices } => Arc::new(Triangle::new(
+                point(vertices[0]),
+                point(vertices[1]),
+                point(vertices[2]),
+                material,
+            )),
+            ShapeDescription::Mesh { path } => {
+                // instances of the same mesh share its geometry
+                let key = (path.clone(), self.material.get_ref().clone());
+                match meshes.get(&key) {
+                    Some(mesh) => mesh.clone(),
+                    None => {
+                        let mesh = Arc::new(
+                            TriangleMesh::from_obj(base.join(path), material)
+                                .map_err(|e| format!("could not load mesh `{path}`: {e}"))?,
+                        );
+                        meshes.insert(key, mesh.clone());
+                        mesh
+                    }
+                }
+            }
+            ShapeDescription::Plane { point: p, normal } => {
+                Arc::new(Plane::new(point(*p), point(*normal), material))
+            }
+            ShapeDescription::XyRect { x, y, z } => {
+                Arc::new(XyRect::new(x[0], x[1], y[0], y[1], *z, material))
+            }
+            ShapeDescription::XzRect { x, z, y } => {
+                Arc::new(XzRect::new(x[0], x[1], z[0], z[1], *y, material))
+            }
+            ShapeDescription::YzRect { y, z, x } => {
+                Arc::new(YzRect::new(y[0], y[1], z[0], z[1], *x, material))
+            }
+            ShapeDescription::Cuboid { min, max } => {
+                Arc::new(Cuboid::new(point(*min), point(*max), material))
+            }
+        };
+
+        // the shape only serves as the boundary of a volume
+        Ok(match medium {
+            Some((density, isotropic)) => Arc::new(ConstantMedium::new(object, density, isotropic)),
+            None => object,
+        })
+    }
+
+    /// Place the `object` built by `shape` with the transform and motion, or
+    /// on every frame anew with the keyframes
+    fn place(
+        &self,
+        object: SharedHittable,
+        interpolation: Option<Interpolation>,
+    ) -> Result<AnimatedObject, String> {
+        if !self.keyframes.is_empty() {
+            let interpolation =
+                interpolation.ok_or("`keyframes` need an [animation] table".to_string())?;
+            if self.motion.is_some() {
+                return Err("an object with `keyframes` cannot also have a `motion`".to_string());
+            }
+
+            let transform = self.transform_track(interpolation);
+            for key in &self.keyframes {
+                if transform.matrix(key.frame).inverse().is_none() {
+                    return Err(format!(
+                        "transform at frame {} is not invertible",
+                        key.frame
+                    ));
+                }
+            }
+
+            return Ok(AnimatedObject::Keyframed { object, transform });
+        }
+
+        let object: SharedHittable = match &self.transform {
+            Some(transform) => {
+                let matrix = transform.matrix();
+                if matrix.inverse().is_none() {
+                    return Err("transform is not invertible".to_string());
+                }
+
+                Arc::new(Transformed::new(object, matrix))
+            }
+            None => object,
+        };
+
+        Ok(AnimatedObject::Fixed(match &self.motion {
+            Some(motion) => Arc::new(Moving::new(
+                object,
+                point(motion.translate),
+                motion.time[0],
+                motion.time[1],
+            )),
+            None => object,
+        }))
+    }
+
+    /// the transform over the frames, moved by the keyframes that are given
+    fn transform_track(&self, interpolation: Interpolation) -> TransformTrack {
+        let keys = &self.keyframes;
+        let (translate, rotate, scale) = match &self.transform {
+            Some(transform) => (
+                point(transform.translate),
+                point(transform.rotate),
+                transform.scale(),
+            ),
+            None => (point([0.; 3]), point([0.; 3]), point([1.; 3])),
+        };
+
+        TransformTrack {
+            translate: track(
+                keys.iter()
+                    .filter_map(|key| key.translate.map(|t| (key.frame, point(t))))
+                    .collect(),
+                translate,
+                interpolation,
+            ),
+            rotate: track(
+                keys.iter()
+                    .filter_map(|key| key.rotate.map(|r| (key.frame, point(r))))
+                    .collect(),
+                rotate,
+                interpolation,
+            ),
+            scale: track(
+                keys.iter()
+                    .filter_map(|key| key.scale.as_ref().map(|s| (key.frame, s.factors())))
+                    .collect(),
+                scale,
+                interpolation,
+            ),
+        }
+    }
+}
+
+impl Scene {
+    /// Load a scene from a TOML scene description file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, SceneError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        Self::parse_relative_to(&source, path.parent().unwrap_or(Path::new(".")))
+    }
+
+    /// Build a scene from the contents of a TOML scene description
+    pub fn parse(source: &str) -> Result<Self, SceneError> {
+        Self::parse_relative_to(source, Path::new("."))
+    }
+
+    /// Build a scene, resolving the paths of mesh files relative to `base`
+    fn parse_relative_to(source: &str, base: &Path) -> Result<Self, SceneError> {
+        let description: SceneDescription =
+            toml::from_str(source).map_err(|e| SceneError::at(source, e.span(), e.message()))?;
+
+        let image = description.image.get_ref();
+        let (height, aspect_ratio) = match (image.height, image.aspect_ratio) {
+            (Some(height), _) => (height, image.width as f64 / height as f64),
+            (None, Some(aspect_ratio)) if aspect_ratio <= 0. => {
+                return Err(SceneError::at(
+                    source,
+                    Some(description.image.span()),
+                    "`aspect_ratio` must be greater than 0",
+                ))
+            }
+            (None, Some(aspect_ratio)) => {
+                ((image.width as f64 / aspect_ratio) as usize, aspect_ratio)
+            }
+            (None, None) => {
+                return Err(SceneError::at(
+                    source,
+                    Some(description.image.span()),
+                    "[image] needs either `height` or `aspect_ratio`",
+                ))
+            }
+        };
+        if image.width == 0 || height == 0 {
+            return Err(SceneError::at(
+                source,
+                Some(description.image.span()),
+                format!(
+                    "the image is {} by {height} pixels, it needs at least one",
+                    image.width
+                ),
+            ));
+        }
+
+        let animation = description.animation.as_ref();
+        let interpolation = animation.map(|animation| match animation.get_ref().interpolation {
+            InterpolationDescription::Linear => Interpolation::Linear,
+            InterpolationDescription::Spline => Interpolation::Spline,
+        });
+        let frames = match animation {
+            Some(animation) => {
+                let [first, last] = animation.get_ref().frames;
+                if first > last {
+                    return Err(SceneError::at(
+                        source,
+                        Some(animation.span()),
+                        "the first of the `frames` comes after the last",
+                    ));
+                }
+                first..=last
+            }
+            None => 0..=0,
+        };
+        // a still is the first frame of an animation without keyframes
+        let frame = *frames.start() as f64;
+
+        let rig = description
+            .camera
+            .get_ref()
+            .rig(
+                animation.map_or(&[], |animation| &animation.get_ref().camera),
+                interpolation.unwrap_or_default(),
+                (image.width, height),
+                aspect_ratio,
+            )
+            .map_err(|message| SceneError::at(source, Some(description.camera.span()), message))?;
+
+        let materials = description
+            .materials
+            .iter()
+            .map(|(name, material)| {
+                let material = material
+                    .get_ref()
+                    .build(base)
+                    .map_err(|message| SceneError::at(source, Some(material.span()), message))?;
+                Ok((name.as_str(), material))
+            })
+            .collect::<Result<HashMap<&str, Material>, SceneError>>()?;
+
+        let mut objects = Vec::new();
+        let mut meshes = MeshCache::new();
+        for object in &description.objects {
+            let error = |message| SceneError::at(source, Some(object.span()), message);
+
+            let name = &object.get_ref().material;
+            let material = materials
+                .get(name.get_ref().as_str())
+                .ok_or_else(|| {
+                    SceneError::at(
+                        source,
+                        Some(name.span()),
+                        format!("unknown material `{}`", name.get_ref()),
+                    )
+                })?
+                .clone();
+
+            let description = object.get_ref();
+            if let Some(density) = &description.density {
+                if density.get_ref().is_nan() || *density.get_ref() <= 0. {
+                    return Err(SceneError::at(
+                        source,
+                        Some(density.span()),
+                        "`density` must be greater than 0",
+                    ));
+                }
+            }
+            let shape = description
+                .shape(material, base, &mut meshes)
+                .map_err(error)?;
+            objects.push(description.place(shape, interpolation).map_err(error)?);
+        }
+
+        let bvh = Bvh::new(
+            objects
+                .iter()
+                .filter_map(|object| object.at(frame))
+                .collect(),
+        );
+        let integrator = match (&description.integrator, &description.shading) {
+            (Some(_), Some(shading)) => {
+                return Err(SceneError::at(
+                    source,
+                    Some(shading.span()),
+                    "give either `shading` or an [integrator]",
+                ))
+            }
+            (Some(integrator), None) => integrator.build(),
+            (None, Some(shading)) => shading.get_ref().integrator().build(),
+            (None, None) => IntegratorDescription::default().build(),
+        };
+        let mut display = description.display.build();
+        let camera = description.camera.get_ref();
+        if let Some(stops) = camera
+            .exposure()
+            .map_err(|message| SceneError::at(source, Some(description.camera.span()), message))?
+        {
+            display.exposure += stops;
+        }
+
+        Ok(Self {
+            aspect_ratio,
+            width: image.width,
+            height,
+            samples_per_pixel: image.samples_per_pixel,
+            adaptive: image.adaptive.as_ref().map(|adaptive| AdaptiveSampling {
+                threshold: adaptive.threshold,
+                min_samples: adaptive.min_samples,
+            }),
+            sampler: match image.sampler {
+                SamplerDescription::Independent => SamplerKind::Independent,
+                SamplerDescription::Stratified => SamplerKind::Stratified,
+                SamplerDescription::Halton => SamplerKind::Halton,
+                SamplerDescription::Sobol => SamplerKind::Sobol,
+            },
+            depth_max: image.max_depth,
+            camera: rig.camera(frame, aspect_ratio, &bvh),
+            objects: bvh,
+            background: match description.background {
+                BackgroundDescription::Sky(_) => Background::Sky,
+                BackgroundDescription::Color(c) => Background::Color(color(c)),
+            },
+            lights: description
+                .lights
+                .iter()
+                .map(LightDescription::build)
+                .collect(),
+            integrator,
+            display,
+            denoiser: description.denoise.as_ref().map(DenoiseDescription::build),
+            animation: interpolation.map(|_| Animation {
+                frames,
+                camera: rig,
+                objects,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::Hittable;
+
+    const SCENE: &str = r#"
+[image]
+width = 300
+aspect_ratio = 1.5
+samples_per_pixel = 10
+max_depth = 5
+
+[camera]
+look_from = [0.0, 0.0, 1.0]
+look_at = [0.0, 0.0, 0.0]
+vfov = 90.0
+
+[materials.glass]
+type = "dielectric"
+refraction_index = 1.5
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, -1.0]
+radius = 0.5
+material = "glass"
+"#;
+
+    /// an image and a camera for the tests that are about something else
+    const IMAGE: &str = r#"
+[image]
+width = 30
+height = 20
+samples_per_pixel = 1
+max_depth = 5
+"#;
+
+    const CAMERA: &str = r#"
+[camera]
+look_from = [0.0, 0.0, 1.0]
+look_at = [0.0, 0.0, 0.0]
+vfov = 90.0
+"#;
+
+    /// line, column and message of the error in `source`
+    fn parse_error(source: &str) -> (usize, usize, String) {
+        match Scene::parse(source) {
+            Err(SceneError::Parse {
+                line,
+                column,
+                message,
+            }) => (line, column, message),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let scene = Scene::parse(SCENE).unwrap();
+
+        assert_eq!(scene.width, 300);
+        assert_eq!(scene.height, 200);
+        assert_eq!(scene.objects.len(), 1);
+        assert_eq!(scene.display, DisplayTransform::default());
+        assert_eq!(scene.adaptive, None);
+        assert_eq!(scene.sampler, SamplerKind::Sobol);
+        assert_eq!(scene.denoiser, None);
+    }
+
+    #[test]
+    fn image_size() {
+        let image = |width: usize, aspect_ratio: f64| {
+            format!(
+                "
+[image]
+width = {width}
+aspect_ratio = {aspect_ratio:?}
+samples_per_pixel = 1
+max_depth = 5
+{CAMERA}"
+            )
+        };
+
+        let (line, _, message) = parse_error(&image(1, 1.5));
+        assert_eq!(line, 2);
+        assert_eq!(message, "the image is 1 by 0 pixels, it needs at least one");
+
+        let (line, _, message) = parse_error(&image(30, -1.5));
+        assert_eq!(line, 2);
+        assert_eq!(message, "`aspect_ratio` must be greater than 0");
+    }
+
+    #[test]
+    fn display() {
+        let source = r#"
+[display]
+exposure = -1.0
+tone_map = "reinhard"
+"#;
+        let display = Scene::parse(&format!("{IMAGE}{CAMERA}{source}"))
+            .unwrap()
+            .display;
+
+        assert_eq!(display.exposure, -1.);
+        assert_eq!(display.tone_map, ToneMap::Reinhard);
+    }
+
+    #[test]
+    fn adaptive() {
+        let source = r#"
+[image]
+width = 30
+height = 20
+samples_per_pixel = 1
+max_depth = 5
+adaptive = { threshold = 0.05 }
+"#;
+        let adaptive = Scene::parse(&format!("{source}{CAMERA}"))
+            .unwrap()
+            .adaptive
+            .unwrap();
+
+        assert_eq!(adaptive.threshold, 0.05);
+        assert_eq!(adaptive.min_samples, 16);
+    }
+
+    #[test]
+    fn sampler() {
+        let source = r#"
+[image]
+width = 30
+height = 20
+samples_per_pixel = 1
+max_depth = 5
+sampler = "halton"
+"#;
+        let scene = Scene::parse(&format!("{source}{CAMERA}")).unwrap();
+
+        assert_eq!(scene.sampler, SamplerKind::Halton);
+    }
+
+    #[test]
+    fn denoise() {
+        let source = r#"
+[denoise]
+iterations = 3
+"#;
+        let denoiser = Scene::parse(&format!("{IMAGE}{CAMERA}{source}"))
+            .unwrap()
+            .denoiser
+            .unwrap();
+
+        assert_eq!(denoiser.iterations, 3);
+        assert_eq!(denoiser.sigma_color, Denoiser::default().sigma_color);
+    }
+
+    #[test]
+    fn orthographic_camera() {
+        let camera = r#"
+[camera]
+type = "orthographic"
+look_from = [0.0, 0.0, 1.0]
+look_at = [0.0, 0.0, 0.0]
+"#;
+        let (line, _, message) = parse_error(&format!("{IMAGE}{camera}"));
+        assert_eq!(line, 8);
+        assert_eq!(message, "an orthographic camera needs a `height`");
+
+        assert!(Scene::parse(&format!("{IMAGE}{camera}height = 2.0\n")).is_ok());
+    }
+
+    #[test]
+    fn example_scene() {
+        let scene = Scene::from_file("scenes/three_spheres.toml").unwrap();
+
+        assert_eq!(scene.objects.len(), 4);
+    }
+
+    #[test]
+    fn unknown_material() {
+        let source = SCENE.replace("material = \"glass\"", "material = \"steel\"");
+        let (line, column, message) = parse_error(&source);
+
+        assert_eq!((line, column), (21, 12));
+        assert_eq!(message, "unknown material `steel`");
+    }
+
+    #[test]
+    fn transform() {
+        let source = SCENE.replace(
+            "material = \"glass\"",
+            "material = \"glass\"\ntransform = { translate = [1.0, 0.0, 0.0], scale = 2.0 }",
+        );
+        let scene = Scene::parse(&source).unwrap();
+        let bbox = scene.objects.bounding_box().unwrap();
+
+        assert!((bbox.min[0] - 0.).abs() < 1e-12);
+        assert!((bbox.max[0] - 2.).abs() < 1e-12);
+        assert!((bbox.min[2] + 3.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn textures() {
+        let source = r#"
+[materials.marble]
+type = "lambertian"
+
+[materials.marble.albedo]
+type = "checker"
+even = [1.0, 1.0, 1.0]
+odd = { type = "marble", scale = 4.0 }
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, -1.0]
+radius = 0.5
+material = "marble"
+"#;
+        assert!(Scene::parse(&format!("{IMAGE}{CAMERA}{source}")).is_ok());
+
+        let source = r#"
+[materials.steel]
+type = "metal"
+albedo = { type = "image", path = "missing.png" }
+fuzz = 0.0
+"#;
+        let (line, _, message) = parse_error(&format!("{IMAGE}{CAMERA}{source}"));
+        assert_eq!(line, 13);
+        assert!(message.starts_with("could not load image `missing.png`"));
+    }
+
+    #[test]
+    fn motion() {
+        let source = r#"
+[camera]
+look_from = [0.0, 0.0, 1.0]
+look_at = [0.0, 0.0, 0.0]
+vfov = 90.0
+shutter = [0.0, 0.5]
+
+[materials.glass]
+type = "dielectric"
+refraction_index = 1.5
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, -1.0]
+radius = 0.5
+material = "glass"
+motion = { translate = [0.0, 1.0, 0.0], time = [0.0, 0.5] }
+"#;
+        let scene = Scene::parse(&format!("{IMAGE}{source}")).unwrap();
+        let bbox = scene.objects.bounding_box().unwrap();
+
+        assert_eq!((bbox.min[1], bbox.max[1]), (-0.5, 1.5));
+    }
+
+    #[test]
+    fn animation() {
+        let object = r#"
+[materials.glass]
+type = "dielectric"
+refraction_index = 1.5
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, -1.0]
+radius = 0.5
+material = "glass"
+keyframes = [
+    { frame = 1, translate = [0.0, 0.0, 0.0] },
+    { frame = 11, translate = [2.0, 0.0, 0.0] },
+]
+"#;
+        let (_, _, message) = parse_error(&format!("{IMAGE}{CAMERA}{object}"));
+        assert_eq!(message, "`keyframes` need an [animation] table");
+
+        let animation = r#"
+[animation]
+frames = [1, 11]
+
+[[animation.camera]]
+frame = 1
+vfov = 90.0
+
+[[animation.camera]]
+frame = 11
+vfov = 45.0
+"#;
+        let mut scene = Scene::parse(&format!("{IMAGE}{CAMERA}{animation}{object}")).unwrap();
+        let animation = scene.animation.take().unwrap();
+        assert_eq!(animation.frames, 1..=11);
+        assert_eq!(scene.objects.bounding_box().unwrap().min[0], -0.5);
+
+        animation.apply(&mut scene, 6);
+        let bbox = scene.objects.bounding_box().unwrap();
+        assert!((bbox.min[0] - 0.5).abs() < 1e-12);
+        assert_eq!(animation.camera.vfov.at(6.), 67.5);
+    }
+
+    /// a camera with the lens `settings` instead of a `vfov`
+    fn lens_camera(settings: &str) -> String {
+        format!(
+            "{IMAGE}
+[camera]
+look_from = [0.0, 0.0, 1.0]
+look_at = [0.0, 0.0, 0.0]
+{settings}
+"
+        )
+    }
+
+    #[test]
+    fn lens() {
+        let lens = "focal_length = 50.0\nf_number = 2.0";
+        assert_eq!(
+            Scene::parse(&lens_camera(lens)).unwrap().display.exposure,
+            0.
+        );
+
+        // f/2 for 1/4 s at ISO 100 is EV100 4
+        let source = lens_camera(&format!("{lens}\nexposure_time = 0.25"));
+        let display = Scene::parse(&source).unwrap().display;
+        assert!((display.exposure + 4. + 1.2f32.log2()).abs() < 1e-6);
+
+        let (_, _, message) = parse_error(&lens_camera("vfov = 90.0\nfocal_length = 50.0"));
+        assert_eq!(message, "give either `vfov` or `focal_length`");
+
+        let (_, _, message) = parse_error(&lens_camera("focal_length = 50.0\nf_number = 0.0"));
+        assert_eq!(message, "`f_number` must be greater than 0");
+
+        let zoom = "[animation]\nframes = [1, 2]\n\n[[animation.camera]]\nframe = 2\nvfov = 45.0";
+        let (_, _, message) = parse_error(&lens_camera(&format!("{lens}\n{zoom}")));
+        assert_eq!(message, "give either `vfov` keys or a `focal_length`");
+    }
+
+    #[test]
+    fn autofocus() {
+        // a small sphere in the upper half of the image, 1.8 to 2 away, in
+        // front of `look_at` at 1
+        let scene = r#"
+[animation]
+frames = [1, 1]
+
+[materials.glass]
+type = "dielectric"
+refraction_index = 1.5
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.3, -1.0]
+radius = 0.2
+material = "glass"
+"#;
+        let source = lens_camera(&format!(
+            "focal_length = 50.0\nautofocus = [15, 4]\n{scene}"
+        ));
+        let scene = Scene::parse(&source).unwrap();
+        let rig = &scene.animation.as_ref().unwrap().camera;
+        let focus_distance = rig.focus_distance(1., scene.aspect_ratio, &scene.objects);
+
+        assert!((1.8..2.).contains(&focus_distance), "{focus_distance}");
+    }
+
+    #[test]
+    fn volume() {
+        let volume = |material: &str, density: &str| {
+            format!(
+                r#"{IMAGE}{CAMERA}
+[materials.smoke]
+{material}
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, -1.0]
+radius = 0.5
+material = "smoke"
+density = {density}
+"#
+            )
+        };
+        let isotropic = "type = \"isotropic\"\nalbedo = [1.0, 1.0, 1.0]";
+
+        let glass = "type = \"dielectric\"\nrefraction_index = 1.5";
+        let (_, _, message) = parse_error(&volume(glass, "2.0"));
+        assert_eq!(
+            message,
+            "a volume with a `density` needs an isotropic material"
+        );
+
+        assert!(Scene::parse(&volume(isotropic, "2.0")).is_ok());
+
+        let (line, column, message) = parse_error(&volume(isotropic, "0.0"));
+        assert_eq!((line, column), (22, 11));
+        assert_eq!(message, "`density` must be greater than 0");
+    }
+
+    #[test]
+    fn unknown_field() {
+        let source = SCENE.replace("radius = 0.5", "radius = 0.5\ncolour = 1.0");
+
+        assert!(Scene::parse(&source).is_err());
+    }
+
+    #[test]
+    fn syntax_error() {
+        let source = SCENE.replace("width = 300", "width = ");
+
+        match Scene::parse(&source) {
+            Err(SceneError::Parse { line, column, .. }) => assert_eq!((line, column), (3, 9)),
+            _ => panic!("expected a parse error"),
+        }
+    }
+}
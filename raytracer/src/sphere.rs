@@ -1,11 +1,14 @@
+use std::f64::consts::PI;
+
 use crate::{
+    aabb::Aabb,
     hittable::{HitRecord, Hittable},
     material::Material,
     point3d::{DotProduct, Length, Normalize, Point3D},
     ray::Ray,
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Sphere {
     pub center: Point3D, // center of sphere
     pub radius: f64,     // radius of sphere
@@ -37,42 +40,175 @@ impl Sphere {
     }
 }
 
-impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let oc = ray.origin - self.center;
+/// Surface coordinates of a point on the unit sphere: `u` is the angle around
+/// the y axis starting from -x, `v` the angle from -y up to +y, both in [0, 1].
+pub fn spherical_uv(p: Point3D) -> (f64, f64) {
+    let theta = (-p[1]).clamp(-1., 1.).acos();
+    let phi = (-p[2]).atan2(p[0]) + PI;
+
+    (phi / (2. * PI), theta / PI)
+}
+
+/// Intersect the sphere around `center` with the given `radius`
+fn hit_sphere<'a>(
+    center: Point3D,
+    radius: f64,
+    material: &'a Material,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let oc = ray.origin - center;
+
+    let a = ray.direction.length_squared();
+    let b_half = oc.dot(&ray.direction);
+    let c = oc.length_squared() - radius * radius;
 
-        let a = ray.direction.length_squared();
-        let b_half = oc.dot(&ray.direction);
-        let c = oc.length_squared() - self.radius * self.radius;
+    let discriminant = b_half * b_half - a * c;
 
-        let discriminant = b_half * b_half - a * c;
+    // no intersection
+    if discriminant < 0.0 {
+        return None;
+    }
 
-        // no intersection
-        if discriminant < 0.0 {
+    // find the neaest root that lies in the acceptable range
+    let d_sqrt = discriminant.sqrt();
+    let mut root = (-b_half - d_sqrt) / a;
+    if root < t_min || root > t_max {
+        root = (-b_half + d_sqrt) / a;
+        if root < t_min || root > t_max {
             return None;
         }
+    }
 
-        // find the neaest root that lies in the acceptable range
-        let d_sqrt = discriminant.sqrt();
-        let mut root = (-b_half - d_sqrt) / a;
-        if root < t_min || root > t_max {
-            root = (-b_half + d_sqrt) / a;
-            if root < t_min || root > t_max {
-                return None;
-            }
+    // found a hit, so return the intersection
+    let p = ray.at(root);
+    let normal = (p - center) / radius;
+    let front_face = ray.direction.dot(&normal) < 0.0;
+    let (u, v) = spherical_uv(normal);
+
+    Some(HitRecord {
+        point: p,
+        normal: if front_face { normal } else { -normal },
+        t: root,
+        u,
+        v,
+        front_face,
+        material,
+    })
+}
+
+fn sphere_bounding_box(center: Point3D, radius: f64) -> Aabb {
+    let r = Point3D::new(radius, radius, radius);
+
+    Aabb::new(center - r, center + r)
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_sphere(self.center, self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(sphere_bounding_box(self.center, self.radius))
+    }
+}
+
+/// Sphere moving in a straight line from `center0` at `time0` to `center1` at
+/// `time1`, staying put before and after
+#[derive(Clone)]
+pub struct MovingSphere {
+    pub center0: Point3D,
+    pub center1: Point3D,
+    pub time0: f64,
+    pub time1: f64,
+    pub radius: f64,
+    pub material: Material,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center0: Point3D,
+        center1: Point3D,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: Material,
+    ) -> Self {
+        Self {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material,
+        }
+    }
+
+    pub fn center(&self, time: f64) -> Point3D {
+        if self.time1 <= self.time0 {
+            return self.center0;
         }
 
-        // found a hit, so return the intersection
-        let p = ray.at(root);
-        let normal = (p - self.center) / self.radius;
-        let front_face = ray.direction.dot(&normal) < 0.0;
-
-        Some(HitRecord {
-            point: p,
-            normal: if front_face { normal } else { -normal },
-            t: root,
-            front_face,
-            material: self.material,
-        })
+        let s = ((time - self.time0) / (self.time1 - self.time0)).clamp(0., 1.);
+        self.center0 + s * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_sphere(
+            self.center(ray.time),
+            self.radius,
+            &self.material,
+            ray,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let start = sphere_bounding_box(self.center0, self.radius);
+        let end = sphere_bounding_box(self.center1, self.radius);
+
+        Some(start.surrounding(&end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Lambertian};
+
+    #[test]
+    fn uv() {
+        let uv = |x, y, z| spherical_uv(Point3D::new(x, y, z));
+
+        assert_eq!(uv(-1., 0., 0.), (0., 0.5));
+        assert_eq!(uv(0., 0., 1.), (0.25, 0.5));
+        assert_eq!(uv(1., 0., 0.), (0.5, 0.5));
+        assert_eq!(uv(0., -1., 0.).1, 0.);
+        assert_eq!(uv(0., 1., 0.).1, 1.);
+    }
+
+    #[test]
+    fn moving() {
+        let sphere = MovingSphere::new(
+            Point3D::new(0., 0., 0.),
+            Point3D::new(0., 2., 0.),
+            0.,
+            1.,
+            0.5,
+            Material::Lambertian(Lambertian::new(Color::white())),
+        );
+        let ray =
+            |time| Ray::new(Point3D::new(5., 1., 0.), Point3D::new(-1., 0., 0.)).with_time(time);
+
+        assert!(sphere.hit(&ray(0.), 0.001, f64::INFINITY).is_none());
+        assert_eq!(sphere.hit(&ray(0.5), 0.001, f64::INFINITY).unwrap().t, 4.5);
+        assert!(sphere.hit(&ray(1.), 0.001, f64::INFINITY).is_none());
+
+        let bbox = sphere.bounding_box().unwrap();
+        assert_eq!((bbox.min[1], bbox.max[1]), (-0.5, 2.5));
     }
 }
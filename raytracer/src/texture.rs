@@ -0,0 +1,177 @@
+use std::{path::Path, sync::Arc};
+
+use crate::{color::Color, perlin::Perlin, point3d::Point3D};
+
+/// Color that varies over a surface, looked up by the surface coordinates
+/// (u, v) and the hit point
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, point: &Point3D) -> Color;
+}
+
+pub type SharedTexture = Arc<dyn Texture>;
+
+/// Same color everywhere
+pub struct SolidColor(pub Color);
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _point: &Point3D) -> Color {
+        self.0
+    }
+}
+
+/// 3D checkerboard of cubes with sides `scale`, alternating between two textures
+pub struct Checker {
+    pub even: SharedTexture,
+    pub odd: SharedTexture,
+    pub scale: f64,
+}
+
+impl Checker {
+    pub fn new(even: SharedTexture, odd: SharedTexture, scale: f64) -> Self {
+        Self { even, odd, scale }
+    }
+
+    pub fn from_colors(even: Color, odd: Color, scale: f64) -> Self {
+        Self::new(Arc::new(SolidColor(even)), Arc::new(SolidColor(odd)), scale)
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, point: &Point3D) -> Color {
+        let cell: i64 = (0..3)
+            .map(|axis| (point[axis] / self.scale).floor() as i64)
+            .sum();
+
+        if cell.rem_euclid(2) == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum NoiseKind {
+    /// plain Perlin noise
+    Smooth,
+    /// several octaves of noise
+    Turbulence,
+    /// sine stripes along z at frequency `scale`, distorted by turbulence
+    Marble,
+}
+
+/// Grey-scale Perlin noise at frequency `scale`
+pub struct Noise {
+    pub perlin: Perlin,
+    pub scale: f64,
+    pub kind: NoiseKind,
+}
+
+impl Noise {
+    pub fn new(kind: NoiseKind, scale: f64) -> Self {
+        Self {
+            perlin: Perlin::default(),
+            scale,
+            kind,
+        }
+    }
+}
+
+impl Texture for Noise {
+    fn value(&self, _u: f64, _v: f64, point: &Point3D) -> Color {
+        let p = self.scale * *point;
+
+        let value = match self.kind {
+            NoiseKind::Smooth => 0.5 * (1. + self.perlin.noise(&p)),
+            NoiseKind::Turbulence => self.perlin.turbulence(&p, 7),
+            NoiseKind::Marble => 0.5 * (1. + (p[2] + 10. * self.perlin.turbulence(point, 7)).sin()),
+        };
+
+        Color::white() * value as f32
+    }
+}
+
+/// Image mapped onto the surface coordinates, with (0, 0) the bottom left corner
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    /// linear colors, row by row from the top
+    pixels: Vec<Color>,
+}
+
+impl ImageTexture {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        let image = image::open(path)?.into_rgb8();
+
+        Ok(Self {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            pixels: image
+                .pixels()
+                .map(|p| {
+                    Color(
+                        srgb_to_linear(p[0]),
+                        srgb_to_linear(p[1]),
+                        srgb_to_linear(p[2]),
+                    )
+                })
+                .collect(),
+        })
+    }
+}
+
+/// undo the sRGB transfer function of an 8-bit channel
+fn srgb_to_linear(value: u8) -> f32 {
+    let c = value as f32 / 255.;
+
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _point: &Point3D) -> Color {
+        if self.pixels.is_empty() {
+            // cyan, to stand out as a debugging aid
+            return Color(0., 1., 1.);
+        }
+
+        let u = u.clamp(0., 1.);
+        // image rows go top to bottom
+        let v = 1. - v.clamp(0., 1.);
+
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+
+        self.pixels[j * self.width + i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checker() {
+        let checker = Checker::from_colors(Color::white(), Color::black(), 1.);
+
+        assert_eq!(
+            checker.value(0., 0., &Point3D::new(0.5, 0.5, 0.5)),
+            Color::white()
+        );
+        assert_eq!(
+            checker.value(0., 0., &Point3D::new(1.5, 0.5, 0.5)),
+            Color::black()
+        );
+        assert_eq!(
+            checker.value(0., 0., &Point3D::new(-0.5, 0.5, 0.5)),
+            Color::black()
+        );
+        assert_eq!(
+            checker.value(0., 0., &Point3D::new(-0.5, -0.5, 0.5)),
+            Color::white()
+        );
+    }
+}
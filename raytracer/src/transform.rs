@@ -0,0 +1,147 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    matrix::Matrix4,
+    point3d::{Normalize, Point3D},
+    ray::Ray,
+    sampler::Sampler,
+};
+
+/// Places an object in the world with an affine transformation.
+///
+/// Rays are intersected with the untransformed object in its own (object)
+/// space; wrap an `Arc` to place several instances of the same geometry.
+pub struct Transformed<H> {
+    pub object: H,
+    /// object space to world space
+    transform: Matrix4,
+    /// world space to object space
+    inverse: Matrix4,
+    /// transforms normals to world space
+    normal_transform: Matrix4,
+}
+
+impl<H: Hittable> Transformed<H> {
+    /// # Panics
+    ///
+    /// Panics if `transform` is not invertible
+    pub fn new(object: H, transform: Matrix4) -> Self {
+        Self::try_new(object, transform).expect("transformation must be invertible")
+    }
+
+    /// `None` if `transform` is not invertible
+    pub fn try_new(object: H, transform: Matrix4) -> Option<Self> {
+        let inverse = transform.inverse()?;
+
+        Some(Self {
+            object,
+            transform,
+            inverse,
+            normal_transform: inverse.transpose(),
+        })
+    }
+
+    /// `hit` the object with the ray in object space, and bring the hit back
+    /// to world space
+    fn hit_transformed<'a>(
+        &'a self,
+        ray: &Ray,
+        hit: impl FnOnce(&'a H, &Ray) -> Option<HitRecord<'a>>,
+    ) -> Option<HitRecord<'a>> {
+        // the direction is not normalized, so t is the same in both spaces
+        let object_ray = Ray::new(
+            self.inverse.transform_point(ray.origin),
+            self.inverse.transform_vector(ray.direction),
+        )
+        .with_time(ray.time);
+
+        let mut hit = hit(&self.object, &object_ray)?;
+        hit.point = self.transform.transform_point(hit.point);
+        hit.normal = self
+            .normal_transform
+            .transform_vector(hit.normal)
+            .normalize();
+
+        Some(hit)
+    }
+
+    pub fn transform(&self) -> &Matrix4 {
+        &self.transform
+    }
+}
+
+impl<H: Hittable> Hittable for Transformed<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.hit_transformed(ray, |object, ray| object.hit(ray, t_min, t_max))
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.hit_transformed(ray, |object, ray| {
+            object.hit_sampled(ray, t_min, t_max, sampler)
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bbox = self.object.bounding_box()?;
+
+        let corners = (0..8).map(|i| {
+            let corner = Point3D::new(
+                if i & 1 == 0 { bbox.min[0] } else { bbox.max[0] },
+                if i & 2 == 0 { bbox.min[1] } else { bbox.max[1] },
+                if i & 4 == 0 { bbox.min[2] } else { bbox.max[2] },
+            );
+            let corner = self.transform.transform_point(corner);
+
+            Aabb::new(corner, corner)
+        });
+
+        corners.reduce(|a, b| a.surrounding(&b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        material::{Lambertian, Material},
+        point3d::Length,
+        sphere::Sphere,
+    };
+
+    #[test]
+    fn scaled_sphere() {
+        let sphere = Sphere::new(
+            Point3D::new(0., 0., 0.),
+            1.,
+            Material::Lambertian(Lambertian::new(Color::white())),
+        );
+        let ellipsoid = Transformed::new(
+            sphere,
+            Matrix4::translation(Point3D::new(0., 0., -5.))
+                * Matrix4::scaling(Point3D::new(2., 1., 1.)),
+        );
+
+        let ray = Ray::new(Point3D::new(10., 0., -5.), Point3D::new(-1., 0., 0.));
+        let hit = ellipsoid.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((hit.t - 8.).abs() < 1e-12);
+        assert!((hit.point[0] - 2.).abs() < 1e-12);
+        assert!(hit.front_face);
+
+        // on x^2 / 4 + y^2 + z^2 = 1 the normal is along (x / 4, y, z)
+        let ray = Ray::new(Point3D::new(1., 5., -5.), Point3D::new(0., -1., 0.));
+        let hit = ellipsoid.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        let expected = Point3D::new(0.25, 3f64.sqrt() / 2., 0.).normalize();
+        assert!((hit.normal - expected).length() < 1e-12);
+
+        let bbox = ellipsoid.bounding_box().unwrap();
+        assert!((bbox.min[0] + 2.).abs() < 1e-12);
+        assert!((bbox.max[2] + 4.).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    point3d::{CrossProduct, DotProduct, Length, Normalize, Point3D},
+    ray::Ray,
+};
+
+/// smallest sine of an angle that is not considered zero, between the edges
+/// of a triangle or between a triangle and a ray
+const MIN_SINE: f64 = 1e-9;
+
+/// Möller–Trumbore ray/triangle intersection
+///
+/// Returns the ray parameter `t` and the barycentric coordinates `(b1, b2)`
+/// of the hit with respect to the vertices `v1` and `v2`.
+pub fn intersect(
+    v0: Point3D,
+    v1: Point3D,
+    v2: Point3D,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+
+    let p = ray.direction.cross(&edge2);
+    let determinant = edge1.dot(&p);
+
+    // ray is parallel to the triangle, or the triangle has no area; the
+    // determinant grows with the lengths of the edges and the direction, so
+    // compare relative to them to not depend on the scale of the scene
+    let scale = ray.direction.length_squared() * edge1.length_squared() * edge2.length_squared();
+    if determinant * determinant <= MIN_SINE * MIN_SINE * scale {
+        return None;
+    }
+
+    let inverse_determinant = 1. / determinant;
+    let s = ray.origin - v0;
+    let b1 = s.dot(&p) * inverse_determinant;
+    if !(0. ..=1.).contains(&b1) {
+        return None;
+    }
+
+    let q = s.cross(&edge1);
+    let b2 = ray.direction.dot(&q) * inverse_determinant;
+    if b2 < 0. || b1 + b2 > 1. {
+        return None;
+    }
+
+    let t = edge2.dot(&q) * inverse_determinant;
+    if t < t_min || t > t_max {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+/// whether three points lie on a line, so that the triangle between them has no area
+pub fn is_degenerate(v0: Point3D, v1: Point3D, v2: Point3D) -> bool {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+
+    edge1.cross(&edge2).length_squared()
+        <= MIN_SINE * MIN_SINE * edge1.length_squared() * edge2.length_squared()
+}
+
+/// bounding box of three points, padded so that axis-aligned triangles are not flat
+pub fn bounding_box(v0: Point3D, v1: Point3D, v2: Point3D) -> Aabb {
+    let padding = Point3D::new(1e-6, 1e-6, 1e-6);
+
+    Aabb::new(v0 - padding, v0 + padding)
+        .surrounding(&Aabb::new(v1 - padding, v1 + padding))
+        .surrounding(&Aabb::new(v2 - padding, v2 + padding))
+}
+
+#[derive(Clone)]
+pub struct Triangle {
+    pub vertices: [Point3D; 3],
+    pub material: Material,
+}
+
+impl Triangle {
+    pub fn new(v0: Point3D, v1: Point3D, v2: Point3D, material: Material) -> Self {
+        Self {
+            vertices: [v0, v1, v2],
+            material,
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let [v0, v1, v2] = self.vertices;
+        let (t, b1, b2) = intersect(v0, v1, v2, ray, t_min, t_max)?;
+
+        let normal = (v1 - v0).cross(&(v2 - v0)).normalize();
+        let front_face = ray.direction.dot(&normal) < 0.0;
+
+        Some(HitRecord {
+            point: ray.at(t),
+            normal: if front_face { normal } else { -normal },
+            t,
+            u: b1,
+            v: b2,
+            front_face,
+            material: &self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let [v0, v1, v2] = self.vertices;
+
+        Some(bounding_box(v0, v1, v2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Lambertian};
+
+    #[test]
+    fn hit() {
+        let triangle = Triangle::new(
+            Point3D::new(0., 0., 0.),
+            Point3D::new(1., 0., 0.),
+            Point3D::new(0., 1., 0.),
+            Material::Lambertian(Lambertian::new(Color::white())),
+        );
+
+        let front = Ray::new(Point3D::new(0.25, 0.25, 1.), Point3D::new(0., 0., -1.));
+        let hit = triangle.hit(&front, 0.001, f64::INFINITY).unwrap();
+        assert_eq!(hit.t, 1.);
+        assert!(hit.front_face);
+        assert_eq!(hit.normal[2], 1.);
+
+        let back = Ray::new(Point3D::new(0.25, 0.25, -1.), Point3D::new(0., 0., 1.));
+        let hit = triangle.hit(&back, 0.001, f64::INFINITY).unwrap();
+        assert!(!hit.front_face);
+        assert_eq!(hit.normal[2], -1.);
+
+        let miss = Ray::new(Point3D::new(0.75, 0.75, 1.), Point3D::new(0., 0., -1.));
+        assert!(triangle.hit(&miss, 0.001, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn scale() {
+        // hits do not depend on the size of the scene
+        for size in [1e-5, 1., 1e5] {
+            let triangle = Triangle::new(
+                Point3D::new(0., 0., 0.),
+                Point3D::new(size, 0., 0.),
+                Point3D::new(0., size, 0.),
+                Material::Lambertian(Lambertian::new(Color::white())),
+            );
+            let ray = Ray::new(
+                Point3D::new(0.25 * size, 0.25 * size, size),
+                Point3D::new(0., 0., -size),
+            );
+            let hit = triangle.hit(&ray, 0.001, f64::INFINITY).unwrap();
+            assert!((hit.t - 1.).abs() < 1e-12);
+        }
+
+        // while a triangle without area is never hit
+        let line = Triangle::new(
+            Point3D::new(0., 0., 0.),
+            Point3D::new(1., 0., 0.),
+            Point3D::new(2., 0., 0.),
+            Material::Lambertian(Lambertian::new(Color::white())),
+        );
+        let ray = Ray::new(Point3D::new(0.5, 0., 1.), Point3D::new(0., 0., -1.));
+        assert!(line.hit(&ray, 0.001, f64::INFINITY).is_none());
+        assert!(is_degenerate(
+            line.vertices[0],
+            line.vertices[1],
+            line.vertices[2]
+        ));
+    }
+}